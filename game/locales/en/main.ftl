@@ -0,0 +1,161 @@
+# English messages. Syntax: a subset of Fluent (https://projectfluent.org), see src/locale.rs.
+# Toggles receive `$state` (on / off); counts select their plural form.
+
+language-name = English
+
+## Main menu
+
+game-title = CONCRETE ANNIHILATOR
+menu-subtitle = Survive a maximum of waves!
+menu-start = Start
+menu-settings = Settings
+menu-credits = Credits
+menu-shop = Shop
+menu-editor = Editor
+
+## Game
+
+pause = Pause
+resume = Resume
+give-up = Give up
+back = Back
+lost-title = You lose!
+retry = Retry
+lost-score = Score: { $score }
+best-score = Best: { $best }
+new-record = New high score!
+lost-wave = Reached wave { $wave }
+lost-combo = { $count ->
+    [one] Best combo: 1 brick
+   *[other] Best combo: { $count } bricks
+}
+wave-title = Wave n°{ $wave }
+hud-score = Score
+hud-wave = Wave { $wave }
+hud-bricks = { $count ->
+    [one] 1 brick
+   *[other] { $count } bricks
+}
+hud-balls = Balls: { $count }/{ $max }
+hud-coins = Coins: { $count }
+hud-combo = Combo ×{ $multiplier }
+hud-speed = Speed ×{ $speed }
+hud-recall = Recall
+hud-powerups = Power-ups
+hud-damage = Dmg: { $count }
+hud-size = Size: { $count }px
+hud-rows = { $count ->
+    [0] Last row!
+    [one] 1 row left
+   *[other] { $count } rows left
+}
+bonus-boom = Boom!
+bonus-balls = { $count ->
+    [one] +1 ball!
+   *[other] +{ $count } balls!
+}
+bonus-damage = More damage!
+bonus-size = Bigger balls!
+notice-recording = Recording to { $path }
+notice-recording-saved = Recording saved to { $path }
+notice-clip-saved = Round saved to { $path }
+notice-screenshot-saved = Screenshot saved to { $path }
+
+## Settings
+
+settings-title = Settings
+settings-preview = { $state ->
+    [on] Preview: On
+   *[off] Preview: Off
+}
+settings-assist = Assist: { $level ->
+    [low] low
+    [high] high
+   *[medium] medium
+}
+settings-language = Language: { language-name }
+settings-reset = Reset keys
+settings-audio = Audio
+settings-display = Display
+binding = { $action }: { $keys }
+press-a-key = press a key (5 s)...
+key-taken = { $key } is already bound to { $action }
+reset-confirm = Reset all keys to defaults?
+reset = Reset
+cancel = Cancel
+keys-reset = Keys reset to defaults
+
+action-aim_left = Aim left
+action-aim_right = Aim right
+action-fine_aim = Fine aim
+action-launch = Launch
+action-pause = Pause
+action-recall = Recall
+action-fast_forward = Fast forward
+action-mute = Mute
+action-record = Record video
+action-screenshot = Screenshot
+action-save_clip = Save round GIF
+action-fullscreen = Fullscreen
+action-back = Back
+
+## Audio
+
+audio-title = Audio
+audio-master = Master: { $percent }%
+audio-music = Music: { $percent }%
+audio-effects = Effects: { $percent }%
+audio-mute = { $state ->
+    [on] Sound: muted
+   *[off] Sound: on
+}
+
+## Display
+
+display-title = Display
+display-low-effects = { $state ->
+    [on] Effects: low
+   *[off] Effects: full
+}
+display-screen-shake = Screen shake: { $state ->
+    [on] on
+   *[off] off
+}
+display-hit-stop = Hit-stop: { $state ->
+    [on] on
+   *[off] off
+}
+display-ball-trails = Ball trails: { $state ->
+    [on] on
+   *[off] off
+}
+display-reduce-motion = Reduce motion: { $state ->
+    [on] on
+   *[off] off
+}
+display-fullscreen = Fullscreen: { $state ->
+    [on] on
+   *[off] off
+}
+display-brick-patterns = Brick patterns: { $state ->
+    [on] on
+   *[off] off
+}
+display-colors = Colors: { $preset ->
+    [deuteranopia] deuteranopia
+    [protanopia] protanopia
+    [tritanopia] tritanopia
+    [high_contrast] high contrast
+   *[theme] theme
+}
+display-theme = Theme: { $theme }
+
+## Other screens
+
+credits-title = Credits
+credits-engine = Made with Rust and SDL2
+credits-fonts = Fonts: Marlboro, Bulgaria
+credits-music = Music: retro-game-arcade-236133
+shop-title = Shop
+editor-title = Level editor
+coming-soon = Coming soon
@@ -0,0 +1,160 @@
+# Messages en français. Les messages absents sont repris de l'anglais.
+
+language-name = Français
+
+## Menu principal
+
+game-title = ANNIHILATEUR DE BÉTON
+menu-subtitle = Survivez au plus de vagues possible !
+menu-start = Jouer
+menu-settings = Réglages
+menu-credits = Crédits
+menu-shop = Boutique
+menu-editor = Éditeur
+
+## Partie
+
+pause = Pause
+resume = Reprendre
+give-up = Abandonner
+back = Retour
+lost-title = Perdu !
+retry = Rejouer
+lost-score = Score : { $score }
+best-score = Record : { $best }
+new-record = Nouveau record !
+lost-wave = Vague { $wave } atteinte
+lost-combo = { $count ->
+    [one] Meilleur combo : { $count } brique
+   *[other] Meilleur combo : { $count } briques
+}
+wave-title = Vague n°{ $wave }
+hud-score = Score
+hud-wave = Vague { $wave }
+hud-bricks = { $count ->
+    [one] { $count } brique
+   *[other] { $count } briques
+}
+hud-balls = Balles : { $count }/{ $max }
+hud-coins = Pièces : { $count }
+hud-combo = Combo ×{ $multiplier }
+hud-speed = Vitesse ×{ $speed }
+hud-recall = Rappel
+hud-powerups = Bonus
+hud-damage = Dégâts : { $count }
+hud-size = Taille : { $count }px
+hud-rows = { $count ->
+    [0] Dernière ligne !
+    [one] 1 ligne restante
+   *[other] { $count } lignes restantes
+}
+bonus-boom = Boum !
+bonus-balls = { $count ->
+    [one] +{ $count } balle !
+   *[other] +{ $count } balles !
+}
+bonus-damage = Plus de dégâts !
+bonus-size = Balles plus grosses !
+notice-recording = Enregistrement dans { $path }
+notice-recording-saved = Enregistrement sauvegardé dans { $path }
+notice-clip-saved = Manche sauvegardée dans { $path }
+notice-screenshot-saved = Capture sauvegardée dans { $path }
+
+## Réglages
+
+settings-title = Réglages
+settings-preview = { $state ->
+    [on] Trajectoire : oui
+   *[off] Trajectoire : non
+}
+settings-assist = Aide : { $level ->
+    [low] faible
+    [high] forte
+   *[medium] moyenne
+}
+settings-language = Langue : { language-name }
+settings-reset = Touches
+settings-audio = Son
+settings-display = Affichage
+binding = { $action } : { $keys }
+press-a-key = appuyez sur une touche (5 s)...
+key-taken = { $key } sert déjà à : { $action }
+reset-confirm = Remettre les touches par défaut ?
+reset = Remettre
+cancel = Annuler
+keys-reset = Touches par défaut rétablies
+
+action-aim_left = Viser à gauche
+action-aim_right = Viser à droite
+action-fine_aim = Visée fine
+action-launch = Lancer
+action-pause = Pause
+action-recall = Rappeler
+action-fast_forward = Avance rapide
+action-mute = Couper le son
+action-record = Filmer
+action-screenshot = Capture d'écran
+action-save_clip = GIF de la manche
+action-fullscreen = Plein écran
+action-back = Retour
+
+## Son
+
+audio-title = Son
+audio-master = Général : { $percent } %
+audio-music = Musique : { $percent } %
+audio-effects = Effets : { $percent } %
+audio-mute = { $state ->
+    [on] Son : coupé
+   *[off] Son : actif
+}
+
+## Affichage
+
+display-title = Affichage
+display-low-effects = { $state ->
+    [on] Effets : réduits
+   *[off] Effets : complets
+}
+display-screen-shake = Tremblements : { $state ->
+    [on] oui
+   *[off] non
+}
+display-hit-stop = Arrêt sur coup : { $state ->
+    [on] oui
+   *[off] non
+}
+display-ball-trails = Traînées : { $state ->
+    [on] oui
+   *[off] non
+}
+display-reduce-motion = Moins d'animations : { $state ->
+    [on] oui
+   *[off] non
+}
+display-fullscreen = Plein écran : { $state ->
+    [on] oui
+   *[off] non
+}
+display-brick-patterns = Motifs des briques : { $state ->
+    [on] oui
+   *[off] non
+}
+display-colors = Couleurs : { $preset ->
+    [deuteranopia] deutéranopie
+    [protanopia] protanopie
+    [tritanopia] tritanopie
+    [high_contrast] contraste élevé
+   *[theme] thème
+}
+display-theme = Thème : { $theme }
+
+## Autres écrans
+
+credits-title = Crédits
+credits-engine = Fait avec Rust et SDL2
+credits-fonts = Polices : Marlboro, Bulgaria
+credits-music = Musique : retro-game-arcade-236133
+shop-title = Boutique
+editor-title = Éditeur de niveaux
+coming-soon = Bientôt disponible
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{self, GameError};
+
+pub const ASSETS_ENV: &str = "BRICK_BREAKER_ASSETS";
+pub const MANIFEST: &str = "assets.txt";
+// Names the game cannot start without; their files may still be optional (the level is
+// written on exit).
+const REQUIRED: [&str; 2] = ["texture.ball", "level.current"];
+
+// Assets are loaded by logical name; the assets.txt file at the root
+// maps each name to a path relative to that root. A name prefixed with `?` is
+// optional: the game can do without it (sounds, fallback font).
+pub struct Assets {
+    root: PathBuf,
+    entries: HashMap<String, PathBuf>
+}
+
+impl Assets {
+    // Search order: --assets <dir>, the BRICK_BREAKER_ASSETS variable, then the
+    // executable's directory and its parents (including their game/ subfolder for
+    // `cargo run`), and finally the current directory.
+    pub fn find_root(args: &[String]) -> PathBuf {
+        if let Some(root) = crate::arg_value(args, "--assets") {
+            return PathBuf::from(root);
+        }
+
+        if let Ok(root) = env::var(ASSETS_ENV) {
+            return PathBuf::from(root);
+        }
+
+        if let Ok(exe) = env::current_exe() {
+            for dir in exe.ancestors().skip(1) {
+                for candidate in [dir.to_path_buf(), dir.join("game")] {
+                    if candidate.join(MANIFEST).is_file() {
+                        return candidate;
+                    }
+                }
+            }
+        }
+
+        let current = PathBuf::from(".");
+        if !current.join(MANIFEST).is_file() && Path::new("game").join(MANIFEST).is_file() {
+            return PathBuf::from("game");
+        }
+        current
+    }
+
+    // Checks every manifest entry and returns a single error listing each missing
+    // required file or name; missing optional ones are only logged.
+    pub fn load(root: PathBuf) -> Result<Self, GameError> {
+        let manifest_path = root.join(MANIFEST);
+        let manifest = fs::read_to_string(&manifest_path)
+            .map_err(|e| GameError::Assets(format!("cannot read asset manifest {}: {} (use --assets <dir> or {})", manifest_path.display(), e, ASSETS_ENV)))?;
+
+        let mut entries: HashMap<String, PathBuf> = HashMap::new();
+        let mut missing: Vec<String> = Vec::new();
+        let mut missing_optional: Vec<String> = Vec::new();
+
+        for (number, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, path) = match line.split_once('=') {
+                Some((name, path)) => (name.trim(), root.join(path.trim())),
+                None => return Err(GameError::Assets(format!("{}:{}: expected `name = path`", manifest_path.display(), number + 1)))
+            };
+
+            let (name, optional) = match name.strip_prefix('?') {
+                Some(name) => (name.trim(), true),
+                None => (name, false)
+            };
+
+            if !path.exists() {
+                let line = format!("    {} -> {}", name, path.display());
+                if optional { missing_optional.push(line) } else { missing.push(line) }
+            }
+            entries.insert(String::from(name), path);
+        }
+
+        for name in REQUIRED {
+            if !entries.contains_key(name) {
+                missing.push(format!("    {} (not listed in {})", name, MANIFEST));
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(GameError::Assets(format!("{} missing asset(s) in {}:\n{}", missing.len(), root.display(), missing.join("\n"))));
+        }
+
+        if !missing_optional.is_empty() {
+            error::log(&format!("{} optional asset(s) missing in {}, continuing without them:\n{}", missing_optional.len(), root.display(), missing_optional.join("\n")));
+        }
+
+        Ok(Assets { root, entries })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // For optional assets that may not be in the manifest at all.
+    pub fn get(&self, name: &str) -> Option<&Path> {
+        self.entries.get(name).map(|path| path.as_path()).filter(|path| path.exists())
+    }
+
+    pub fn path(&self, name: &str) -> Result<&Path, GameError> {
+        self.entries.get(name).map(|path| path.as_path())
+            .ok_or_else(|| GameError::Assets(format!("asset `{}` is not listed in {}", name, self.root.join(MANIFEST).display())))
+    }
+}
@@ -0,0 +1,578 @@
+use rand::Rng;
+use sdl2::mixer::{Channel, Chunk, InitFlag, Music, Sdl2MixerContext, AUDIO_S16LSB};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::assets::Assets;
+use crate::error;
+use crate::settings::Settings;
+
+pub const SAMPLE_RATE: i32 = 44_100;
+pub const CHANNELS: i32 = 2;
+const LAYER_CHANNEL: i32 = 0;
+// Key of the music in the captured mix, which does not use a mixer channel.
+const MUSIC_VOICE: i32 = -1;
+const SFX_VOICES: i32 = 12;
+const FADE_MS: i32 = 1_500;
+// SDL_mixer streams a single music, so two tracks cannot overlap: on a switch, the
+// previous one fades out quickly before the next one fades in.
+const SWITCH_FADE_MS: i32 = 400;
+const MUSIC_EXTENSIONS: [&str; 4] = ["mp3", "ogg", "flac", "wav"];
+// Loop faded in over the game music in the danger zone when no `music.layer` is given.
+const PULSE_BPM: usize = 140;
+const PULSE_BEATS: usize = 4;
+pub const MAX_VOLUME: i32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Cue {
+    MenuMusic,
+    GameMusic,
+    BallLaunch,
+    BrickHit,
+    BrickBroken,
+    Explosion,
+    BricksDown,
+    WaveStart,
+    BonusBalls,
+    BonusDamage,
+    BonusSize
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bus {
+    Music,
+    Sfx
+}
+
+impl Cue {
+    pub const ALL: [Cue; 11] = [
+        Cue::MenuMusic, Cue::GameMusic, Cue::BallLaunch, Cue::BrickHit, Cue::BrickBroken, Cue::Explosion,
+        Cue::BricksDown, Cue::WaveStart, Cue::BonusBalls, Cue::BonusDamage, Cue::BonusSize
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cue::MenuMusic => "menu",
+            Cue::GameMusic => "game",
+            Cue::BallLaunch => "ball_launch",
+            Cue::BrickHit => "brick_hit",
+            Cue::BrickBroken => "brick_broken",
+            Cue::Explosion => "explosion",
+            Cue::BricksDown => "bricks_down",
+            Cue::WaveStart => "wave_start",
+            Cue::BonusBalls => "bonus_balls",
+            Cue::BonusDamage => "bonus_damage",
+            Cue::BonusSize => "bonus_size"
+        }
+    }
+
+    pub fn bus(&self) -> Bus {
+        match self {
+            Cue::MenuMusic | Cue::GameMusic => Bus::Music,
+            _ => Bus::Sfx
+        }
+    }
+
+    // Asset name in the manifest: music.<name> (a folder of tracks) or sound.<name>.
+    fn asset(&self) -> String {
+        match self.bus() {
+            Bus::Music => format!("{}{}", "music.", self.name()),
+            Bus::Sfx => format!("{}{}", "sound.", self.name())
+        }
+    }
+
+    // The sound's own volume, from 0 to 128, before the buses are applied.
+    fn volume(&self) -> i32 {
+        match self {
+            Cue::MenuMusic => 60,
+            Cue::GameMusic => 30,
+            Cue::BrickBroken => 80,
+            Cue::BricksDown => 50,
+            Cue::WaveStart => 90,
+            _ => 60
+        }
+    }
+
+    // A sound can only take the voice of a sound with a strictly lower priority.
+    fn priority(&self) -> u8 {
+        match self {
+            Cue::BallLaunch | Cue::BrickHit => 0,
+            Cue::BrickBroken | Cue::BricksDown => 1,
+            _ => 2
+        }
+    }
+
+    fn max_voices(&self) -> usize {
+        match self {
+            Cue::BallLaunch | Cue::BrickHit => 3,
+            Cue::BrickBroken => 4,
+            _ => 2
+        }
+    }
+}
+
+// Sound requests emitted by the simulation and the screens, played once per frame.
+pub enum AudioEvent {
+    Play(Cue),
+    Music(Cue),
+    PauseMusic,
+    ResumeMusic,
+    StopMusic,
+    Intensity(bool)
+}
+
+// A sound playing in the captured mix for recording.
+struct Voice {
+    cue: Cue,
+    samples: Rc<[i16]>,
+    position: usize,
+    looping: bool,
+    paused: bool
+}
+
+pub struct Audio {
+    _context: Option<Sdl2MixerContext>,
+    enabled: bool,
+    chunks: HashMap<Cue, Chunk>,
+    voices: Vec<Option<Cue>>,
+    playlists: HashMap<Cue, Vec<PathBuf>>,
+    layer: Option<Chunk>,
+    music: Option<Cue>,
+    track: Option<Music<'static>>,
+    track_path: Option<PathBuf>,
+    paused: bool,
+    intense: bool,
+    capture: Option<HashMap<i32, Voice>>,
+    volumes: (i32, i32, i32, bool)
+}
+
+impl Audio {
+    // Without an audio device (missing or busy), the game runs silently.
+    pub fn open(assets: &Assets) -> Self {
+        let context = sdl2::mixer::init(InitFlag::MP3 | InitFlag::FLAC | InitFlag::MOD | InitFlag::OGG)
+            .map_err(|e| error::log(&format!("mixer: {}", e)))
+            .ok();
+
+        let enabled = match sdl2::mixer::open_audio(SAMPLE_RATE, AUDIO_S16LSB, CHANNELS, 1_024) {
+            Ok(()) => true,
+            Err(e) => {
+                error::log(&format!("audio disabled: {}", e));
+                false
+            }
+        };
+
+        let mut chunks: HashMap<Cue, Chunk> = HashMap::new();
+        let mut playlists: HashMap<Cue, Vec<PathBuf>> = HashMap::new();
+        let mut layer: Option<Chunk> = None;
+        if enabled {
+            sdl2::mixer::allocate_channels(SFX_VOICES + 1);
+            sdl2::mixer::reserve_channels(1);
+
+            for cue in Cue::ALL {
+                // Missing optional files were already reported by Assets::load.
+                let path = match assets.get(&cue.asset()) {
+                    Some(path) => path,
+                    None => continue
+                };
+                if cue.bus() == Bus::Music {
+                    playlists.insert(cue, Audio::playlist(path));
+                    continue;
+                }
+                match Chunk::from_file(path) {
+                    Ok(chunk) => {
+                        chunks.insert(cue, chunk);
+                    },
+                    Err(e) => error::log(&format!("{}: {}", cue.asset(), e))
+                }
+            }
+
+            // Layer added on top of the game music when bricks get close to the limit.
+            if let Some(path) = assets.get("music.layer") {
+                layer = Chunk::from_file(path).map_err(|e| error::log(&format!("music.layer: {}", e))).ok();
+            }
+            if layer.is_none() {
+                layer = Chunk::from_raw_buffer(pulse_samples().into_boxed_slice()).map_err(|e| error::log(&format!("pulse layer: {}", e))).ok();
+            }
+        }
+
+        Audio {
+            _context: context,
+            enabled,
+            chunks,
+            voices: vec![None; SFX_VOICES as usize],
+            playlists,
+            layer,
+            music: None,
+            track: None,
+            track_path: None,
+            paused: false,
+            intense: false,
+            capture: None,
+            volumes: (MAX_VOLUME, MAX_VOLUME, MAX_VOLUME, false)
+        }
+    }
+
+    // A folder gives all its tracks, sorted; a single file is a one-track list.
+    fn playlist(path: &Path) -> Vec<PathBuf> {
+        if !path.is_dir() {
+            return if path.is_file() { vec![path.to_path_buf()] } else { Vec::new() };
+        }
+
+        let mut tracks: Vec<PathBuf> = match fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+            Err(e) => {
+                error::log(&format!("cannot read playlist {}: {}", path.display(), e));
+                Vec::new()
+            }
+        };
+        tracks.retain(|track| {
+            track.extension().and_then(|extension| extension.to_str())
+                .is_some_and(|extension| MUSIC_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        });
+        tracks.sort();
+        tracks
+    }
+
+    fn bus_volume(&self, bus: Bus) -> i32 {
+        let (master, music, sfx, muted) = self.volumes;
+        if muted {
+            return 0;
+        }
+        match bus {
+            Bus::Music => master * music,
+            Bus::Sfx => master * sfx
+        }
+    }
+
+    fn cue_volume(&self, cue: Cue) -> i32 {
+        cue.volume() * self.bus_volume(cue.bus()) / (MAX_VOLUME * MAX_VOLUME)
+    }
+
+    fn music_volume(&self) -> i32 {
+        match self.music {
+            Some(cue) => self.cue_volume(cue),
+            None => 0
+        }
+    }
+
+    fn layer_volume(&self) -> i32 {
+        self.cue_volume(Cue::GameMusic)
+    }
+
+    pub fn set_volumes(&mut self, settings: &Settings) {
+        let volumes = (settings.master_volume, settings.music_volume, settings.sfx_volume, settings.muted);
+        if volumes == self.volumes || !self.enabled {
+            self.volumes = volumes;
+            return;
+        }
+        self.volumes = volumes;
+
+        Music::set_volume(self.music_volume());
+        Channel(LAYER_CHANNEL).set_volume(self.layer_volume());
+        for (i, voice) in self.voices.iter().enumerate() {
+            if let Some(cue) = voice {
+                Channel(i as i32 + 1).set_volume(self.cue_volume(*cue));
+            }
+        }
+    }
+
+    pub fn handle(&mut self, event: AudioEvent) {
+        if !self.enabled {
+            return;
+        }
+
+        match event {
+            AudioEvent::Play(cue) => self.play(cue),
+            AudioEvent::Music(cue) => {
+                self.set_intensity(false);
+                if self.music == Some(cue) && !self.paused && Music::is_playing() {
+                    return;
+                }
+                self.music = Some(cue);
+                self.paused = false;
+                self.fade_out(SWITCH_FADE_MS);
+            },
+            AudioEvent::PauseMusic => {
+                self.paused = true;
+                Music::pause();
+                Channel(LAYER_CHANNEL).pause();
+                self.pause_captured(true);
+            },
+            AudioEvent::ResumeMusic => {
+                self.paused = false;
+                Music::resume();
+                Channel(LAYER_CHANNEL).resume();
+                self.pause_captured(false);
+            },
+            AudioEvent::StopMusic => {
+                self.set_intensity(false);
+                self.music = None;
+                self.paused = false;
+                self.fade_out(FADE_MS);
+            },
+            AudioEvent::Intensity(intense) => self.set_intensity(intense)
+        }
+    }
+
+    // The next track only starts once the fade-out is over; a finished
+    // track gives way to another one from the same list.
+    pub fn update(&mut self) {
+        if !self.enabled || self.paused || Music::is_playing() {
+            return;
+        }
+        if let Some(cue) = self.music {
+            self.next_track(cue);
+        }
+    }
+
+    fn fade_out(&mut self, ms: i32) {
+        if let Some(capture) = self.capture.as_mut() {
+            capture.remove(&MUSIC_VOICE);
+        }
+        if Music::is_playing() && !Music::is_paused() {
+            if let Err(e) = Music::fade_out(ms) {
+                error::log(&format!("cannot fade out music: {}", e));
+            }
+        }
+        else {
+            Music::halt();
+        }
+    }
+
+    // Picks a random track, different from the previous one when the list allows it.
+    fn next_track(&mut self, cue: Cue) {
+        let tracks: Vec<&PathBuf> = match self.playlists.get(&cue) {
+            Some(tracks) => tracks.iter().filter(|track| tracks.len() == 1 || Some(*track) != self.track_path.as_ref()).collect(),
+            None => Vec::new()
+        };
+        if tracks.is_empty() {
+            self.music = None;
+            return;
+        }
+
+        let path = tracks[rand::thread_rng().gen_range(0..tracks.len())].clone();
+        match Music::from_file(&path) {
+            Ok(track) => {
+                Music::set_volume(self.music_volume());
+                if let Err(e) = track.fade_in(1, FADE_MS) {
+                    error::log(&format!("cannot play {}: {}", path.display(), e));
+                }
+                self.track = Some(track);
+                self.capture_music(cue, &path);
+            },
+            Err(e) => {
+                error::log(&format!("{}: {}", path.display(), e));
+                if let Some(tracks) = self.playlists.get_mut(&cue) {
+                    tracks.retain(|track| *track != path);
+                }
+            }
+        }
+        self.track_path = Some(path);
+    }
+
+    fn set_intensity(&mut self, intense: bool) {
+        if intense == self.intense {
+            return;
+        }
+        self.intense = intense;
+
+        if let Some(layer) = &self.layer {
+            if let Some(capture) = self.capture.as_mut() {
+                capture.remove(&LAYER_CHANNEL);
+            }
+            if !intense {
+                Channel(LAYER_CHANNEL).fade_out(FADE_MS);
+                return;
+            }
+            Channel(LAYER_CHANNEL).set_volume(self.layer_volume());
+            match Channel(LAYER_CHANNEL).fade_in(layer, -1, FADE_MS) {
+                Ok(_) => {
+                    let samples = Audio::samples(layer);
+                    self.capture_voice(LAYER_CHANNEL, Cue::GameMusic, samples, true);
+                },
+                Err(e) => error::log(&format!("cannot play music.layer: {}", e))
+            }
+        }
+    }
+
+    // Limits the number of voices per sound; when every voice is taken, the sound
+    // replaces the lowest-priority voice, or is not played.
+    fn play(&mut self, cue: Cue) {
+        let chunk = match self.chunks.get(&cue) {
+            Some(chunk) => chunk,
+            None => return
+        };
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            if voice.is_some() && !Channel(i as i32 + 1).is_playing() {
+                *voice = None;
+            }
+        }
+
+        if self.voices.iter().filter(|voice| **voice == Some(cue)).count() >= cue.max_voices() {
+            return;
+        }
+
+        let free = self.voices.iter().position(|voice| voice.is_none());
+        let stolen = || {
+            self.voices.iter().enumerate()
+                .filter_map(|(i, voice)| voice.map(|other| (i, other.priority())))
+                .filter(|(_, priority)| *priority < cue.priority())
+                .min_by_key(|(_, priority)| *priority)
+                .map(|(i, _)| i)
+        };
+
+        let voice = match free.or_else(stolen) {
+            Some(voice) => voice,
+            None => return
+        };
+
+        let channel = Channel(voice as i32 + 1);
+        channel.halt();
+        channel.set_volume(self.cue_volume(cue));
+        match channel.play(chunk, 0) {
+            Ok(_) => {
+                self.voices[voice] = Some(cue);
+                let samples = Audio::samples(chunk);
+                self.capture_voice(voice as i32 + 1, cue, samples, false);
+            },
+            Err(e) => error::log(&format!("cannot play {}: {}", cue.name(), e))
+        }
+    }
+
+    // While recording, the mix is redone in software from the same sounds
+    // and volumes, at the pace of the frames rather than the audio device; fades
+    // are not reproduced.
+    pub fn capture(&mut self, capture: bool) {
+        if !capture {
+            self.capture = None;
+            return;
+        }
+        self.capture = Some(HashMap::new());
+        if let (Some(cue), Some(path)) = (self.music, self.track_path.clone()) {
+            self.capture_music(cue, &path);
+        }
+    }
+
+    // Chunks are already converted to the device format: interleaved S16 stereo.
+    fn samples(chunk: &Chunk) -> Rc<[i16]> {
+        unsafe {
+            let raw = &*chunk.raw;
+            Rc::from(std::slice::from_raw_parts(raw.abuf as *const i16, raw.alen as usize / 2))
+        }
+    }
+
+    fn capture_voice(&mut self, channel: i32, cue: Cue, samples: Rc<[i16]>, looping: bool) {
+        if let Some(capture) = self.capture.as_mut() {
+            capture.insert(channel, Voice { cue, samples, position: 0, looping, paused: false });
+        }
+    }
+
+    // The mixer streams the music; for capture, the whole track is decoded.
+    fn capture_music(&mut self, cue: Cue, path: &Path) {
+        if self.capture.is_none() {
+            return;
+        }
+        match Chunk::from_file(path) {
+            Ok(track) => {
+                let samples = Audio::samples(&track);
+                self.capture_voice(MUSIC_VOICE, cue, samples, false);
+            },
+            Err(e) => error::log(&format!("cannot capture {}: {}", path.display(), e))
+        }
+    }
+
+    fn pause_captured(&mut self, paused: bool) {
+        if let Some(capture) = self.capture.as_mut() {
+            for channel in [MUSIC_VOICE, LAYER_CHANNEL] {
+                if let Some(voice) = capture.get_mut(&channel) {
+                    voice.paused = paused;
+                }
+            }
+        }
+    }
+
+    // Returns `frames` interleaved stereo samples of the captured mix.
+    pub fn mixdown(&mut self, frames: usize) -> Vec<i16> {
+        let mut mix = vec![0_i32; frames * CHANNELS as usize];
+        let mut capture = match self.capture.take() {
+            Some(capture) => capture,
+            None => return vec![0; mix.len()]
+        };
+
+        for (channel, voice) in capture.iter_mut() {
+            let volume = match *channel {
+                MUSIC_VOICE => self.music_volume(),
+                LAYER_CHANNEL => self.layer_volume(),
+                _ => self.cue_volume(voice.cue)
+            };
+            if voice.paused || voice.samples.is_empty() {
+                continue;
+            }
+            for sample in mix.iter_mut() {
+                if voice.position >= voice.samples.len() {
+                    if !voice.looping {
+                        break;
+                    }
+                    voice.position = 0;
+                }
+                *sample += voice.samples[voice.position] as i32 * volume / 128;
+                voice.position += 1;
+            }
+        }
+        capture.retain(|_, voice| voice.looping || voice.position < voice.samples.len());
+        self.capture = Some(capture);
+
+        mix.iter().map(|sample| (*sample).clamp(i16::MIN as i32, i16::MAX as i32) as i16).collect()
+    }
+}
+
+// A heartbeat in the device format (interleaved S16 stereo): a kick falling from 90 to
+// 45 Hz on every beat and a short noise tick between beats.
+fn pulse_samples() -> Vec<i16> {
+    let rate = SAMPLE_RATE as f32;
+    let beat = SAMPLE_RATE as usize * 60 / PULSE_BPM;
+    let mut noise: u32 = 0x2545_f491;
+    let mut samples = Vec::with_capacity(beat * PULSE_BEATS * CHANNELS as usize);
+
+    for i in 0..beat * PULSE_BEATS {
+        let t = (i % beat) as f32 / rate;
+        let phase = 45.0 * t + 45.0 * (1.0 - (-20.0 * t).exp()) / 20.0;
+        let kick = (-12.0 * t).exp() * (2.0 * std::f32::consts::PI * phase).sin();
+
+        noise = noise.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        let tick_t = ((i + beat / 2) % beat) as f32 / rate;
+        let tick = (-60.0 * tick_t).exp() * ((noise >> 16) as f32 / 32_768.0 - 1.0);
+
+        let value = ((0.55 * kick + 0.12 * tick) * i16::MAX as f32) as i16;
+        for _ in 0..CHANNELS {
+            samples.push(value);
+        }
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulse_is_a_whole_number_of_beats() {
+        let samples = pulse_samples();
+        let beat = SAMPLE_RATE as usize * 60 / PULSE_BPM;
+        assert_eq!(samples.len(), beat * PULSE_BEATS * CHANNELS as usize);
+        assert!(samples.chunks(2).all(|frame| frame[0] == frame[1]));
+    }
+
+    #[test]
+    fn pulse_is_audible_and_loops_quietly() {
+        let samples = pulse_samples();
+        let peak = samples.iter().map(|sample| (*sample as i32).abs()).max().unwrap();
+        assert!(peak > i16::MAX as i32 / 4);
+        // Both ends of the loop are near silence, so it repeats without a click.
+        assert!((samples[0] as i32).abs() < 1_000);
+        assert!((samples[samples.len() - 1] as i32).abs() < 1_000);
+    }
+}
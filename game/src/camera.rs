@@ -0,0 +1,45 @@
+use sdl2::rect::Rect;
+
+use crate::utils::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const MAX_SHAKE: f32 = 8.0;
+const TRAUMA_DECAY: f32 = 0.04;
+
+// View offset for screen shake. The intensity ("trauma") adds up with
+// each shake and decays on its own; the amplitude follows its square so that
+// small shakes stay subtle.
+pub struct Camera {
+    trauma: f32,
+    time: f32
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            trauma: 0.0,
+            time: 0.0
+        }
+    }
+
+    pub fn shake(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    pub fn stop(&mut self) {
+        self.trauma = 0.0;
+    }
+
+    pub fn update(&mut self) {
+        self.trauma = (self.trauma - TRAUMA_DECAY).max(0.0);
+        self.time += 1.0;
+    }
+
+    // Two sine waves of different frequencies give an irregular motion without
+    // any random draw.
+    pub fn viewport(&self) -> Rect {
+        let amplitude = MAX_SHAKE * self.trauma * self.trauma;
+        let x = (amplitude * (self.time * 1.7).sin()).round() as i32;
+        let y = (amplitude * (self.time * 2.3).cos()).round() as i32;
+        Rect::new(x, y, WINDOW_WIDTH, WINDOW_HEIGHT)
+    }
+}
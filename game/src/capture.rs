@@ -0,0 +1,110 @@
+use sdl2::image::SaveSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::GameError;
+use crate::gif;
+
+// One frame out of CLIP_FRAME_STEP is kept, i.e. 20 frames per second.
+pub const CLIP_FRAME_STEP: i32 = 3;
+const CLIP_DELAY: u16 = 5;
+const CLIP_SCALE: usize = 2;
+const MAX_CLIP_FRAMES: usize = 200;
+// Fixed 6 x 7 x 6 level palette: enough for the game's flat colors, with no per-frame quantization.
+const LEVELS: (usize, usize, usize) = (6, 7, 6);
+
+// File name that places the capture in the game: wave, seed and time.
+pub fn file_name(prefix: &str, wave: u32, seed: u64, extension: &str) -> PathBuf {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    PathBuf::from(format!("{}-wave{}-seed{}-{}.{}", prefix, wave, seed, seconds, extension))
+}
+
+// `pixels`: RGB24 image of the canvas, contiguous rows.
+pub fn screenshot(pixels: &mut [u8], width: u32, height: u32, path: &Path) -> Result<(), GameError> {
+    let surface = Surface::from_data(pixels, width, height, width * 3, PixelFormatEnum::RGB24)?;
+    surface.save(path)?;
+    Ok(())
+}
+
+// Last frames of the current round, downscaled and indexed as they are captured so
+// that the buffer stays small.
+pub struct Clip {
+    width: usize,
+    height: usize,
+    frames: VecDeque<Vec<u8>>
+}
+
+impl Clip {
+    pub fn new(width: u32, height: u32) -> Self {
+        Clip {
+            width: width as usize / CLIP_SCALE,
+            height: height as usize / CLIP_SCALE,
+            frames: VecDeque::new()
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // Each clip pixel is the average of a CLIP_SCALE x CLIP_SCALE square of the canvas.
+    pub fn push(&mut self, pixels: &[u8]) {
+        let row = self.width * CLIP_SCALE * 3;
+        let mut frame = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut sum = [0_usize; 3];
+                for dy in 0..CLIP_SCALE {
+                    for dx in 0..CLIP_SCALE {
+                        let offset = (y * CLIP_SCALE + dy) * row + (x * CLIP_SCALE + dx) * 3;
+                        for (channel, total) in sum.iter_mut().enumerate() {
+                            *total += pixels.get(offset + channel).copied().unwrap_or(0) as usize;
+                        }
+                    }
+                }
+                let count = CLIP_SCALE * CLIP_SCALE;
+                frame.push(Clip::index(sum[0] / count, sum[1] / count, sum[2] / count));
+            }
+        }
+
+        if self.frames.len() >= MAX_CLIP_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    fn index(r: usize, g: usize, b: usize) -> u8 {
+        let (levels_r, levels_g, levels_b) = LEVELS;
+        let r = (r * (levels_r - 1) + 127) / 255;
+        let g = (g * (levels_g - 1) + 127) / 255;
+        let b = (b * (levels_b - 1) + 127) / 255;
+        (r * levels_g * levels_b + g * levels_b + b) as u8
+    }
+
+    fn palette() -> Vec<[u8; 3]> {
+        let (levels_r, levels_g, levels_b) = LEVELS;
+        let level = |value: usize, levels: usize| (value * 255 / (levels - 1)) as u8;
+        let mut palette = Vec::new();
+        for r in 0..levels_r {
+            for g in 0..levels_g {
+                for b in 0..levels_b {
+                    palette.push([level(r, levels_r), level(g, levels_g), level(b, levels_b)]);
+                }
+            }
+        }
+        palette
+    }
+
+    pub fn save_gif(&self, path: &Path) -> Result<(), GameError> {
+        let frames: Vec<&[u8]> = self.frames.iter().map(|frame| frame.as_slice()).collect();
+        gif::write(path, self.width as u16, self.height as u16, &Clip::palette(), &frames, CLIP_DELAY)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const LOG_PATH: &str = "brick_breaker.log";
+
+#[derive(Debug)]
+pub enum GameError {
+    Sdl(String),
+    Assets(String),
+    Record(String),
+    Io(io::Error)
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Sdl(message) => write!(f, "SDL error: {}", message),
+            GameError::Assets(message) => write!(f, "{}", message),
+            GameError::Record(message) => write!(f, "recording error: {}", message),
+            GameError::Io(error) => write!(f, "I/O error: {}", error)
+        }
+    }
+}
+
+// SDL functions return their errors as a String.
+impl From<String> for GameError {
+    fn from(message: String) -> Self {
+        GameError::Sdl(message)
+    }
+}
+
+impl From<ffmpeg_next::Error> for GameError {
+    fn from(error: ffmpeg_next::Error) -> Self {
+        GameError::Record(error.to_string())
+    }
+}
+
+impl From<io::Error> for GameError {
+    fn from(error: io::Error) -> Self {
+        GameError::Io(error)
+    }
+}
+
+// Writes to stderr and appends the line to the log file, timestamped in
+// seconds since the epoch. A log file that cannot be opened is not an error.
+pub fn log(message: &str) {
+    eprintln!("{}", message);
+
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(LOG_PATH) {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+        let _ = writeln!(file, "[{}] {}", seconds, message);
+    }
+}
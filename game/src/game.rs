@@ -2,22 +2,84 @@ extern crate sdl2;
 extern crate rand;
 
 use ffmpeg_next::ffi::sqrtf;
-use sdl2::mixer::Chunk;
 use std::fs::File;
-use std::io::{self, BufRead, Write};
+use std::io::Write;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 use crate::utils::*;
-use sdl2::rect::Rect;
+use crate::assets::Assets;
+use crate::audio::{AudioEvent, Cue, MAX_VOLUME};
+use crate::error::{self, GameError};
+use crate::camera::Camera;
+use crate::theme::{ColorPreset, Skin, Theme, THEMES_DIR};
+use crate::particles::{Particles, DEBRIS, SPARKS};
+use crate::tween::{Ease, Tween};
+use crate::score::{HighScore, HighScores, Score, ScoreRules, DEFAULT_MODE, MODES_DIR};
+use crate::settings::{AssistLevel, Settings};
+use crate::text::Text;
+use crate::ui::{fit_text, Anchor, Layout, Ui, UiEvent, Widget};
+use crate::screen::{Screen, ScreenStack};
+use crate::input::{Action, Bindings};
+use crate::locale::{Locale, DEFAULT_LANGUAGE, LOCALES_DIR};
+use sdl2::keyboard::Keycode;
+use sdl2::rect::{Point, Rect};
 use sdl2::pixels::Color;
-use sdl2::render::{Canvas, Texture, TextureCreator, TextureQuery};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
 use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::{Window, WindowContext};
-use std::path::Path;
-use rand::Rng;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::replay::{Launch, Replay, LAST_REPLAY_PATH};
 
 use crate::utils::Brick;
 
+const PREVIEW_MAX_STEPS: u32 = 2000;
+const PREVIEW_DOT_SPACING: u32 = 4;
+const LIMIT_BAR_Y: i32 = 585;
+// Number of descents before the limit bar from which the music intensifies.
+const DANGER_ROWS: i32 = 2;
+// Animation durations, in frames.
+const DESCENT_FRAMES: u32 = 18;
+const DROP_FRAMES: u32 = 24;
+const DROP_ROW_DELAY: u32 = 5;
+const DROP_HEIGHT: f32 = 140.0;
+const TITLE_FRAMES: u32 = 40;
+const COUNTER_FRAMES: u32 = 30;
+// Screen shakes, as a fraction of the maximum intensity.
+const BOMB_SHAKE: f32 = 0.6;
+const DANGER_SHAKE: f32 = 0.3;
+// Hit-stop, in ticks, when a brick that needed at least HIT_STOP_HITS hits is destroyed.
+const HIT_STOP_TICKS: u32 = 4;
+const HIT_STOP_HITS: i32 = 10;
+const TRAIL_ALPHA: u8 = 90;
+// Simulation steps per rendered frame. Long rounds double their speed every
+// AUTO_SPEED_UP_TICKS (10 s at 60 frames per second), fast-forward goes straight to FAST_FORWARD_STEPS.
+const FAST_FORWARD_STEPS: u32 = 4;
+const AUTO_SPEED_UP_TICKS: u64 = 600;
+const MAX_STEPS_PER_FRAME: u32 = 8;
+// Waiting for a key to bind is given up after this long, so that every key can be bound.
+const REBIND_TIMEOUT: Duration = Duration::from_secs(5);
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+// HUD labels on each side of the playfield: name, column (-1 left, 1 right), y and height.
+const HUD_COLUMN_X: i32 = 250;
+const HUD_COLUMN_WIDTH: u32 = 92;
+const HUD_WIDGETS: [(&str, i32, i32, u32); 11] = [
+    ("hud_score_title", -1, 90, 24),
+    ("hud_score", -1, 116, 34),
+    ("hud_wave", -1, 170, 26),
+    ("hud_bricks", -1, 206, 26),
+    ("hud_balls", -1, 242, 26),
+    ("hud_coins", -1, 278, 26),
+    ("hud_combo", -1, 320, 30),
+    ("hud_powerups", 1, 90, 24),
+    ("hud_damage", 1, 126, 26),
+    ("hud_size", 1, 162, 26),
+    ("hud_rows", 1, LIMIT_BAR_Y - 30, 24)
+];
+
 macro_rules! rect(
     ($x:expr, $y:expr, $w:expr, $h:expr) => (
         Rect::new($x as i32, $y as i32, $w as u32, $h as u32)
@@ -25,71 +87,56 @@ macro_rules! rect(
 );
 
 pub(crate) struct DrawnContent {
-    pub(crate) displayed_in_game: bool,
-    pub(crate) displayed_in_pause: bool,
-    pub(crate) displayed_at_loss: bool,
-    pub(crate) name: Option<String>,
     pub(crate) rect: Rect,
     pub(crate) color: Color
 }
 
-pub(crate) struct TexturedContent<'a> {
-    pub(crate) displayed_in_game: bool,
-    pub(crate) displayed_in_pause: bool,
-    pub(crate) displayed_at_loss: bool,
-    pub(crate) name: Option<String>,
-    pub(crate) texture: Texture<'a>,
-    pub(crate) src: Option<Rect>,
-    pub(crate) dst: Option<Rect>
-}
-
 pub(crate) struct Wave <'a> {
     pub(crate) wave_number: u32,
-    pub(crate) title_texture: Texture<'a>,
-    pub(crate) no_title_texture: Texture<'a>,
-    pub(crate) bricks: Vec<Brick<'a>>
+    pub(crate) level_path: PathBuf,
+    pub(crate) title_texture: Rc<Texture<'a>>,
+    pub(crate) title_offset: Tween,
+    pub(crate) bricks: Vec<Brick>,
+    pub(crate) rng: StdRng
 }
 
 impl<'a> Wave <'a> {
-    pub fn new(wave_number: u32, ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) -> Self {
-        let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-
-        let title_surface = font
-            .render(&format!("{}{}", "Wave n°", wave_number.to_string()))
-            .blended(Color::RGBA(180, 120, 120, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let title_texture = texture_creator
-            .create_texture_from_surface(&title_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let no_title_surface = font
-            .render(&format!("{}{}", "Wave n°", wave_number.to_string()))
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let no_title_texture = texture_creator
-            .create_texture_from_surface(&no_title_surface)
-            .map_err(|e| e.to_string()).unwrap();
-        
+    pub fn new(wave_number: u32, level_path: &Path, title: &str, text: &mut Text<'a>, color: Color) -> Self {
         Wave {
             wave_number: wave_number,
-            title_texture: title_texture,
-            no_title_texture: no_title_texture,
-            bricks: Vec::new()
+            level_path: level_path.to_path_buf(),
+            title_texture: text.render(title, color),
+            title_offset: Tween::idle(0.0),
+            bricks: Vec::new(),
+            rng: StdRng::seed_from_u64(0)
         }
     }
 
-    pub fn init_file(&self) {
-        let mut file = File::create(String::from_str(LEVEL_PATH).unwrap()).unwrap();
+    pub fn set_title(&mut self, title: &str, text: &mut Text<'a>, color: Color) {
+        self.render_title(title, text, color);
+        self.title_offset = Tween::new(-300.0, 0.0, TITLE_FRAMES, Ease::OutBack);
+    }
+
+    // Without restarting the animation, after a theme or language change.
+    pub fn render_title(&mut self, title: &str, text: &mut Text<'a>, color: Color) {
+        self.title_texture = text.render(title, color);
+    }
+
+    // Every wave of a game is drawn from this seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // Generates the wave and writes it to the level file; if writing fails, the
+    // generated wave is played anyway.
+    pub fn init_file(&mut self) -> String {
         let mut string_level = "".to_string();
 
         for i in 0..12 {
             if i <= self.wave_number {
                 for _ in 0..12 {
-                    let mut rng = rand::thread_rng();
                     let mut life = 0;
-                    if rng.gen_range(1.0..10.0) <= 4.0 {
+                    if self.rng.gen_range(1.0..10.0) <= 4.0 {
                             life = 100;
                     }
                     string_level.push_str(&life.to_string());
@@ -101,62 +148,56 @@ impl<'a> Wave <'a> {
             }
             string_level.push_str("\n");
         }
-        file.write_all(string_level.as_bytes()).unwrap();
+        if let Err(e) = File::create(&self.level_path).and_then(|mut file| file.write_all(string_level.as_bytes())) {
+            error::log(&format!("cannot write level {}: {}", self.level_path.display(), e));
+        }
+        string_level
     }
 
-    pub fn display(&self, mut canvas: Canvas<Window>, frame: i32) ->Canvas<Window> {
-        if (frame % 60) <= 30 {
-            canvas.copy(&(self.title_texture), None, Some(rect!(50, 20, 250, 50))).unwrap();
-        }
-        else {
-            canvas.copy(&(self.no_title_texture), None, Some(rect!(50, 20, 250, 50))).unwrap();
-        }
+    // The title slides in from the left on every new wave.
+    pub fn display(&self, mut canvas: Canvas<Window>) ->Canvas<Window> {
+        let x = 50 + self.title_offset.value().round() as i32;
+        let _ = canvas.copy(&(self.title_texture), None, Some(rect!(x, 20, 250, 50)));
         
         canvas
     }
 
-    pub fn load_bricks(&mut self,ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) {
-        self.init_file();
-
-        let file = File::open(String::from_str(LEVEL_PATH).unwrap()).unwrap();
-        let reader = io::BufReader::new(file);
+    pub fn load_bricks(&mut self) {
+        let level = self.init_file();
     
-        let mut bricks:Vec<Brick<'a>> = Vec::new();
-        let mut j= 0;
-
-        for line in reader.lines() {
-            match line {
-                Ok(content) => {                
-                    let tmp : Vec<&str> =  content.split(" ").collect();
-                    for i in 0..tmp.len() {
-                        match tmp[i].parse::<u32>() {
-                            Ok(0) => {},
-                            Ok(nombre) => {             
-                                let mut rng = rand::thread_rng();
-                                let mut brick_type = "normal";
-                                if rng.gen_range(1.0..10.0) <= 1.5 {
-                                        let random_type_number = rng.gen_range(1..12);
-
-                                        if random_type_number <= 3 {
-                                            brick_type = "bomb";
-                                        }
-                                        else if random_type_number <= 6 {
-                                            brick_type = "more_balls";
-                                        }
-                                        else if random_type_number <= 9 {
-                                            brick_type = "more_damage";
-                                        }
-                                        else {
-                                            brick_type = "bigger_balls";
-                                        }
+        let mut bricks:Vec<Brick> = Vec::new();
+        let mut j: u32 = 0;
+
+        for content in level.lines() {
+            let tmp : Vec<&str> =  content.split(" ").collect();
+            for i in 0..tmp.len() {
+                match tmp[i].parse::<u32>() {
+                    Ok(0) => {},
+                    Ok(nombre) => {             
+                        let mut brick_type = "normal";
+                        if self.rng.gen_range(1.0..10.0) <= 1.5 {
+                                let random_type_number = self.rng.gen_range(1..12);
+
+                                if random_type_number <= 3 {
+                                    brick_type = "bomb";
+                                }
+                                else if random_type_number <= 6 {
+                                    brick_type = "more_balls";
+                                }
+                                else if random_type_number <= 9 {
+                                    brick_type = "more_damage";
+                                }
+                                else {
+                                    brick_type = "bigger_balls";
                                 }
-                                bricks.push(Brick::new(i as i32,j as i32 , nombre as i32, String::from_str(&brick_type).unwrap(), ttf_context, &texture_creator));
-                            },
-                            _ => {}, 
                         }
-                    }
+                        let mut brick = Brick::new(i as i32,j as i32 , nombre as i32, String::from_str(&brick_type).unwrap());
+                        // The wave falls row by row, the top one first.
+                        brick.offset = Tween::new(-DROP_HEIGHT, 0.0, DROP_FRAMES, Ease::OutCubic).delayed(j * DROP_ROW_DELAY);
+                        bricks.push(brick);
+                    },
+                    _ => {}, 
                 }
-                _ => {}, 
             }
             j = j + 1;
         }
@@ -165,614 +206,956 @@ impl<'a> Wave <'a> {
 }
 
 pub(crate) struct Game<'a> {
-    pub(crate) started: bool,
-    pub(crate) paused: bool,
-    pub(crate) drawn: Vec<DrawnContent>,
-    pub(crate) textured: Vec<TexturedContent<'a>>,
+    pub(crate) screens: ScreenStack,
+    pub(crate) uis: HashMap<Screen, Ui<'a>>,
+    pub(crate) decorations: Vec<DrawnContent>,
+    pub(crate) settings: Settings,
+    pub(crate) rebinding: Option<Action>,
+    pub(crate) rebinding_since: Instant,
+    pub(crate) settings_message: Option<String>,
     pub(crate) angle: Angle,
     pub(crate) balls: Vec<Ball>,
     pub(crate) index: Vec<usize>,
     pub(crate) round: bool,
     pub(crate) balls_in_round: i32,
     pub(crate) game_is_loaded: bool,
-    pub(crate) game_is_lost: bool,
     pub(crate) wave: Wave<'a>,
     pub(crate) max_balls: i32,
     pub(crate) ball_damage: i32,
     pub(crate) ball_size: u32,
     pub(crate) display_bonus: bool,
-    pub(crate) bonus_displayed: Texture<'a>,
+    pub(crate) bonus_displayed: Rc<Texture<'a>>,
+    // Status line (a file saved...) shown over every screen for a moment.
+    pub(crate) notice: Option<(Rc<Texture<'a>>, Instant)>,
     pub(crate) bonus_display_start_time: Instant,
-    pub(crate) damage_texture: Texture<'a>,
-    pub(crate) balls_texture: Texture<'a>,
-    pub(crate) size_texture: Texture<'a>
+    pub(crate) sounds: Vec<AudioEvent>,
+    pub(crate) danger: bool,
+    pub(crate) tick: u64,
+    pub(crate) next_seed: Option<u64>,
+    pub(crate) replay: Replay,
+    pub(crate) particles: Particles,
+    pub(crate) camera: Camera,
+    // Simulation ticks still skipped after a killing blow.
+    pub(crate) hit_stop: u32,
+    // Displayed balls, damage and size, which catch up with the real values.
+    pub(crate) hud_counters: [Tween; 3],
+    // Values shown by the HUD when it was last rendered, none to render it again.
+    pub(crate) hud_values: Option<[i64; 12]>,
+    pub(crate) fast_forward: bool,
+    pub(crate) round_start_tick: u64,
+    pub(crate) score: Score,
+    pub(crate) high_scores: HighScores,
+    // Replays are not entered in the high scores.
+    pub(crate) record_scores: bool,
+    pub(crate) coins: u32,
+    pub(crate) skin: Skin<'a>,
+    pub(crate) themes: Vec<String>,
+    pub(crate) themes_root: PathBuf,
+    pub(crate) locale: Locale,
+    pub(crate) languages: Vec<String>,
+    pub(crate) locales_root: PathBuf,
+    pub(crate) modes_root: PathBuf,
+    // Fonts and ball from the assets, used when the theme does not provide them.
+    pub(crate) fonts: Vec<PathBuf>,
+    pub(crate) ball_path: PathBuf,
+    pub(crate) ttf_context: &'a Sdl2TtfContext,
+    pub(crate) texture_creator: &'a TextureCreator<WindowContext>,
+    pub(crate) text: Text<'a>
 }
 
 impl<'a> Game<'a> {
-    pub fn new(ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) -> Self {
-        let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-        let bonus_surface = font
-            .render("More bullets!")
-            .blended(Color::RGBA(255, 255, 255, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let bonus_texture = texture_creator
-            .create_texture_from_surface(&bonus_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let damage_surface = font
-            .render("Dmg: 5")
-            .blended(Color::RGBA(200, 200, 200, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let damage_texture = texture_creator
-            .create_texture_from_surface(&damage_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let balls_surface = font
-            .render("Balls: 10")
-            .blended(Color::RGBA(200, 200, 200, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let balls_texture = texture_creator
-            .create_texture_from_surface(&balls_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let size_surface = font
-            .render("Size: 10px")
-            .blended(Color::RGBA(200, 200, 200, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let size_texture = texture_creator
-            .create_texture_from_surface(&size_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        
-        Game {
-            started: false,
-            paused: false,
-            drawn: Vec::new(),
-            textured: Vec::new(),
+    pub fn new(assets: &Assets, ttf_context: &'a Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) -> Result<Self, GameError> {
+        let settings = Settings::load();
+        let themes_root = assets.root().join(THEMES_DIR);
+        let theme = Theme::load(&themes_root, &settings.theme).unwrap_or_else(|e| {
+            error::log(&format!("{}, using the classic theme", e));
+            Theme::classic()
+        });
+        let fonts: Vec<PathBuf> = ["font.main", "font.fallback"].iter().filter_map(|name| assets.get(name)).map(|path| path.to_path_buf()).collect();
+        let ball_path = assets.path("texture.ball")?.to_path_buf();
+        let skin = Skin::load(theme, &ball_path, texture_creator)?;
+        let locales_root = assets.root().join(LOCALES_DIR);
+        let locale = Game::load_locale(&locales_root, &settings.language);
+        let modes_root = assets.root().join(MODES_DIR);
+        let rules = Game::load_rules(&modes_root, &settings.mode);
+
+        let mut text = Text::new(&Game::font_paths(&skin, &fonts), ttf_context, texture_creator)?;
+        let bonus_texture = text.render(&locale.format("bonus-balls", &[("count", "3")]), Color::RGBA(255, 255, 255, 255));
+        let title = locale.format("wave-title", &[("wave", "1")]);
+        let wave = Wave::new(1, assets.path("level.current")?, &title, &mut text, skin.theme.title);
+
+        Ok(Game {
+            screens: ScreenStack::new(),
+            uis: HashMap::new(),
+            decorations: Vec::new(),
+            particles: Particles::new(settings.low_effects),
+            camera: Camera::new(),
+            hit_stop: 0,
+            hud_counters: [Tween::idle(10.0), Tween::idle(5.0), Tween::idle(10.0)],
+            hud_values: None,
+            fast_forward: false,
+            round_start_tick: 0,
+            score: Score::new(rules),
+            high_scores: HighScores::load(),
+            record_scores: true,
+            coins: 0,
+            settings,
+            rebinding: None,
+            rebinding_since: Instant::now(),
+            settings_message: None,
             angle: Angle::new(),
             balls: Vec::new(),
             index: Vec::new(),
             round: false,
             balls_in_round: 0,
             game_is_loaded: false,
-            game_is_lost: false,
-            wave: Wave::new(1, &ttf_context, &texture_creator),
+            wave,
             max_balls: 10,
             ball_damage: 5,
             ball_size: 10,
             display_bonus: false,
             bonus_displayed: bonus_texture,
+            notice: None,
             bonus_display_start_time: Instant::now(),
-            damage_texture: damage_texture,
-            balls_texture: balls_texture,
-            size_texture: size_texture
-        }
+            sounds: Vec::new(),
+            danger: false,
+            tick: 0,
+            next_seed: None,
+            replay: Replay::new(0, DEFAULT_MODE),
+            themes: Theme::discover(&themes_root),
+            themes_root,
+            languages: Locale::discover(&locales_root),
+            locales_root,
+            modes_root,
+            locale,
+            skin,
+            fonts,
+            ball_path,
+            ttf_context,
+            texture_creator,
+            text
+        })
     }
 
-    pub fn load_bricks(&mut self,ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) {
-        self.wave.load_bricks(ttf_context, texture_creator);
-        self.game_is_loaded = true;
-
+    fn load_rules(root: &Path, mode: &str) -> ScoreRules {
+        ScoreRules::load(root, mode).unwrap_or_else(|e| {
+            error::log(&format!("{}, using the classic scoring", e));
+            ScoreRules::classic()
+        })
     }
 
-    pub (crate) fn load_content(&mut self, ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>){
-        let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
+    // Scoring rules for the next games; each mode keeps its own high scores.
+    pub(crate) fn set_mode(&mut self, mode: &str) {
+        self.score = Score::new(Game::load_rules(&self.modes_root, mode));
+        self.hud_values = None;
+        self.update_hud();
+    }
 
-        let surface = font
-            .render("CONCRETE ANNIHILATOR")
-            .blended(Color::RGBA(255, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
+    // Unknown or broken languages fall back to English, then to message ids.
+    fn load_locale(root: &Path, language: &str) -> Locale {
+        Locale::load(root, language).unwrap_or_else(|e| {
+            error::log(&format!("{}, using English", e));
+            Locale::load(root, DEFAULT_LANGUAGE).unwrap_or_else(|e| {
+                error::log(&e.to_string());
+                Locale::empty()
+            })
+        })
+    }
 
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string()).unwrap();
+    // The texts are rebuilt in the new language, including the screens already created.
+    pub(crate) fn set_language(&mut self, language: &str) {
+        self.locale = Game::load_locale(&self.locales_root, language);
+        self.settings.language = String::from(language);
+        self.settings.save();
+
+        self.uis.clear();
+        self.load_content();
+        let title = self.wave_title();
+        self.wave.render_title(&title, &mut self.text, self.skin.theme.title);
+        self.hud_values = None;
+        self.update_hud();
+    }
 
-        let TextureQuery { width, height, .. } = texture.query();
-        
-        let title_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("menu_title").unwrap()),
-            texture: texture,
-            src: None,
-            dst: Some(rect!(WINDOW_WIDTH/2 - width/6, 50, width/3, height/3))
-        };
+    fn wave_title(&self) -> String {
+        self.locale.format("wave-title", &[("wave", &self.wave.wave_number.to_string())])
+    }
 
-        self.textured.push(title_textured_content);
+    fn set_wave_title(&mut self) {
+        let title = self.wave_title();
+        self.wave.set_title(&title, &mut self.text, self.skin.theme.title);
+    }
 
-        let subtitle_surface = font
-            .render("Survive a maximum of waves!")
-            .blended(Color::RGBA(255, 255, 255, 255))
-            .map_err(|e| e.to_string()).unwrap();
+    fn action_label(&self, action: Action) -> String {
+        self.locale.get(&format!("{}{}", "action-", action.name()))
+    }
 
-        let subtitle_texture = texture_creator
-            .create_texture_from_surface(&subtitle_surface)
-            .map_err(|e| e.to_string()).unwrap();
+    // The theme's font first, then the asset ones.
+    fn font_paths<'p>(skin: &'p Skin, fonts: &'p [PathBuf]) -> Vec<&'p Path> {
+        skin.theme.font.iter().chain(fonts.iter()).map(|path| path.as_path()).collect()
+    }
 
-        let TextureQuery { width, height, .. } = subtitle_texture.query();
-        
-        let subtitle_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("menu_subtitle").unwrap()),
-            texture: subtitle_texture,
-            src: None,
-            dst: Some(rect!(WINDOW_WIDTH/2 - width/6, 130, width/3, height/3))
+    // Reloads the font, sprites and colors; texts already on screen are rendered again.
+    pub(crate) fn set_theme(&mut self, name: &str) {
+        let skin = match Theme::load(&self.themes_root, name).and_then(|theme| Skin::load(theme, &self.ball_path, self.texture_creator)) {
+            Ok(skin) => skin,
+            Err(e) => {
+                error::log(&format!("cannot switch theme: {}", e));
+                return;
+            }
         };
+        match Text::new(&Game::font_paths(&skin, &self.fonts), self.ttf_context, self.texture_creator) {
+            Ok(text) => self.text = text,
+            Err(e) => error::log(&format!("theme {}: {}", name, e))
+        }
+        self.skin = skin;
+        self.settings.theme = String::from(name);
+        self.settings.save();
 
-        self.textured.push(subtitle_textured_content);
-
-        let start_button = DrawnContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("menu_start").unwrap()),
-            rect: rect!(200, 200, 200, 100),
-            color: Color::RGB(255, 255, 255)
-        };
+        for ui in self.uis.values_mut() {
+            ui.invalidate();
+        }
+        let title = self.wave_title();
+        self.wave.render_title(&title, &mut self.text, self.skin.theme.title);
+        self.hud_values = None;
+        self.update_hud();
+        self.set_decorations();
+        self.set_display_labels();
+    }
 
-        let start_surface = font
-            .render("Start")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let start_texture = texture_creator
-            .create_texture_from_surface(&start_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let start_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: None,
-            texture: start_texture,
-            src: None,
-            dst: Some(rect!(225, 225, 150, 50))
-        };
+    pub(crate) fn screen(&self) -> Screen {
+        self.screens.current()
+    }
 
-        let settings_button = DrawnContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("menu_settings").unwrap()),
-            rect: rect!(200, 350, 200, 100),
-            color: Color::RGB(255, 255, 255)
-        };
+    pub(crate) fn ui(&mut self, screen: Screen) -> &mut Ui<'a> {
+        self.uis.entry(screen).or_insert_with(|| Ui::new((WINDOW_WIDTH, WINDOW_HEIGHT)))
+    }
 
-        let settings_surface = font
-            .render("Settings")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let settings_texture = texture_creator
-            .create_texture_from_surface(&settings_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let settings_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: None,
-            texture: settings_texture,
-            src: None,
-            dst: Some(rect!(225, 375, 150, 50))
-        };
+    pub(crate) fn push_screen(&mut self, screen: Screen) {
+        let from = self.screen();
+        self.on_exit(from, screen);
+        self.screens.push(screen);
+        self.on_enter(screen, from);
+    }
 
-        let credits_button = DrawnContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("menu_credits").unwrap()),
-            rect: rect!(200, 500, 200, 100),
-            color: Color::RGB(255, 255, 255)
-        };
+    pub(crate) fn pop_screen(&mut self) {
+        let from = self.screen();
+        if self.screens.pop().is_some() {
+            let to = self.screen();
+            self.on_exit(from, to);
+            self.on_enter(to, from);
+        }
+    }
 
-        let credits_surface = font
-            .render("Credits")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let credits_texture = texture_creator
-            .create_texture_from_surface(&credits_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let credits_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: None,
-            texture: credits_texture,
-            src: None,
-            dst: Some(rect!(225, 525, 150, 50))
-        };
+    pub(crate) fn set_screen(&mut self, screen: Screen) {
+        let from = self.screen();
+        self.on_exit(from, screen);
+        self.screens.reset(screen);
+        self.on_enter(screen, from);
+    }
 
-        let pause_button = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("pause_button").unwrap()),
-            rect: rect!(420, 15, 150, 40),
-            color: Color::RGB(255, 255, 255)
-        };
+    fn on_enter(&mut self, screen: Screen, from: Screen) {
+        self.ui(screen).reset_focus();
+
+        match screen {
+            Screen::Menu if from != Screen::Settings && from != Screen::Credits && from != Screen::Shop && from != Screen::Editor => {
+                self.sounds.push(AudioEvent::Music(Cue::MenuMusic));
+            },
+            Screen::Playing if from == Screen::Paused => {
+                self.sounds.push(AudioEvent::ResumeMusic);
+            },
+            Screen::Playing => {
+                self.sounds.push(AudioEvent::Music(Cue::GameMusic));
+
+                self.balls = Vec::new();
+                self.particles.clear();
+                self.camera.stop();
+                self.hit_stop = 0;
+                self.fast_forward = false;
+                self.round = false;
+                self.balls_in_round = 0;
+                self.game_is_loaded = false;
+                self.danger = false;
+
+                // A game always starts from the same state, otherwise its replay would not replay it.
+                self.max_balls = 10;
+                self.ball_damage = 5;
+                self.ball_size = 10;
+                self.hud_counters = [Tween::idle(10.0), Tween::idle(5.0), Tween::idle(10.0)];
+                self.score.reset();
+                self.coins = 0;
+                self.wave.wave_number = 1;
+                self.set_wave_title();
+
+                let seed = self.next_seed.take().unwrap_or_else(|| rand::thread_rng().gen());
+                self.wave.reseed(seed);
+                self.replay = Replay::new(seed, &self.score.rules.mode);
+                self.tick = 0;
+                self.update_hud();
+            },
+            Screen::Paused => {
+                self.sounds.push(AudioEvent::PauseMusic);
+            },
+            Screen::Lost => {
+                self.sounds.push(AudioEvent::StopMusic);
+                self.save_replay();
+            },
+            _ => {}
+        }
+    }
 
-        let pause_surface = font
-            .render("Pause")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let pause_texture = texture_creator
-            .create_texture_from_surface(&pause_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let pause_textured_content = TexturedContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: None,
-            texture: pause_texture,
-            src: None,
-            dst: Some(rect!(425, 20, 140, 40))
-        };
+    fn on_exit(&mut self, screen: Screen, to: Screen) {
+        match screen {
+            Screen::Paused if to == Screen::Menu => {
+                self.sounds.push(AudioEvent::StopMusic);
+                self.save_replay();
+            },
+            Screen::Settings => {
+                self.rebinding = None;
+                self.settings_message = None;
+                self.ui(Screen::Settings).close_dialog();
+                self.set_settings_labels();
+            },
+            _ => {}
+        }
+    }
 
-        let resume_button = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: true,
-            displayed_at_loss: false,
-            name: Some(String::from_str("pause_resume").unwrap()),
-            rect: rect!(200, 200, 200, 100),
-            color: Color::RGB(255, 255, 255)
-        };
+    pub fn load_bricks(&mut self) {
+        self.wave.load_bricks();
+        self.game_is_loaded = true;
 
-        let resume_surface = font
-            .render("Resume")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let resume_texture = texture_creator
-            .create_texture_from_surface(&resume_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let resume_textured_content = TexturedContent {
-            displayed_in_game: true,
-            displayed_in_pause: true,
-            displayed_at_loss: false,
-            name: None,
-            texture: resume_texture,
-            src: None,
-            dst: Some(rect!(225, 225, 150, 50))
-        };
+    }
 
-        let giveup_button = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: true,
-            displayed_at_loss: false,
-            name: Some(String::from_str("pause_giveup").unwrap()),
-            rect: rect!(200, 350, 200, 100),
-            color: Color::RGB(255, 255, 255)
-        };
+    pub(crate) fn load_content(&mut self) {
+        let t = |id: &str| self.locale.get(id);
+        let title = t("game-title");
+        let subtitle = t("menu-subtitle");
+        let menu_buttons = [("menu_start", "menu-start"), ("menu_settings", "menu-settings"), ("menu_credits", "menu-credits"), ("menu_shop", "menu-shop"), ("menu_editor", "menu-editor")]
+            .map(|(name, id)| (name, t(id)));
+        let (pause, resume, give_up, back) = (t("pause"), t("resume"), t("give-up"), t("back"));
+        let recall = t("hud-recall");
+        let (lost_title, retry) = (t("lost-title"), t("retry"));
+        let settings_buttons = [("settings_reset", "settings-reset", -155), ("settings_audio", "settings-audio", 0), ("settings_display", "settings-display", 155)]
+            .map(|(name, id, x)| (name, t(id), x));
+        let (settings_title, audio_title, display_title) = (t("settings-title"), t("audio-title"), t("display-title"));
+        let secondary_screens = [
+            (Screen::Credits, "credits_back", t("credits-title"), vec![t("credits-engine"), t("credits-fonts"), t("credits-music")]),
+            (Screen::Shop, "shop_back", t("shop-title"), vec![t("coming-soon")]),
+            (Screen::Editor, "editor_back", t("editor-title"), vec![t("coming-soon")])
+        ];
+
+        let menu = self.ui(Screen::Menu);
+        menu.add(Widget::label("menu_title", &title, Color::RGB(255, 0, 0), Layout::new(Anchor::Top, 0, 40, 420, 70)));
+        menu.add(Widget::label("menu_subtitle", &subtitle, Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 120, 360, 40)));
+
+        for (i, (name, text)) in menu_buttons.iter().enumerate() {
+            menu.add(Widget::button(name, text, Layout::new(Anchor::Top, 0, 190 + 90 * i as i32, 200, 80)));
+        }
 
-        let giveup_surface = font
-            .render("Give up")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let giveup_texture = texture_creator
-            .create_texture_from_surface(&giveup_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let giveup_textured_content = TexturedContent {
-            displayed_in_game: true,
-            displayed_in_pause: true,
-            displayed_at_loss: false,
-            name: None,
-            texture: giveup_texture,
-            src: None,
-            dst: Some(rect!(225, 375, 150, 50))
-        };
+        let playing = self.ui(Screen::Playing);
+        playing.add(Widget::button("pause_button", &pause, Layout::new(Anchor::TopRight, -30, 15, 150, 40)));
+        playing.add(Widget::button("speed_button", "", Layout::new(Anchor::Top, -HUD_COLUMN_X, 540, HUD_COLUMN_WIDTH, 36)));
+        playing.add(Widget::button("recall_button", &recall, Layout::new(Anchor::Top, -HUD_COLUMN_X, 585, HUD_COLUMN_WIDTH, 36)));
+        for (name, column, y, h) in HUD_WIDGETS {
+            playing.add(Widget::label(name, "", Color::RGB(255, 255, 255), Layout::new(Anchor::Top, column * HUD_COLUMN_X, y, HUD_COLUMN_WIDTH, h)));
+        }
 
-        self.textured.push(start_textured_content);
-        self.textured.push(settings_textured_content);
-        self.textured.push(credits_textured_content);
-        self.textured.push(pause_textured_content);
-        self.textured.push(resume_textured_content);
-        self.textured.push(giveup_textured_content);
-        self.drawn.push(start_button);
-        self.drawn.push(settings_button);
-        self.drawn.push(credits_button);
-        self.drawn.push(pause_button);
-        self.drawn.push(resume_button);
-        self.drawn.push(giveup_button);
+        let paused = self.ui(Screen::Paused);
+        paused.add(Widget::button("pause_resume", &resume, Layout::new(Anchor::Top, 0, 200, 200, 100)));
+        paused.add(Widget::button("pause_giveup", &give_up, Layout::new(Anchor::Top, 0, 350, 200, 100)));
+
+        let lost = self.ui(Screen::Lost);
+        lost.add(Widget::label("lost_title", &lost_title, Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 200, 150, 50)));
+        lost.add(Widget::label("lost_score", "", Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 270, 300, 40)));
+        lost.add(Widget::label("best_score", "", Color::RGB(255, 200, 0), Layout::new(Anchor::Top, 0, 320, 300, 30)));
+        lost.add(Widget::label("lost_wave", "", Color::RGB(200, 200, 200), Layout::new(Anchor::Top, 0, 365, 300, 28)));
+        lost.add(Widget::label("lost_combo", "", Color::RGB(200, 200, 200), Layout::new(Anchor::Top, 0, 400, 300, 28)));
+        lost.add(Widget::button("retry_button", &retry, Layout::new(Anchor::Bottom, 0, -125, 200, 100)));
+
+        let preview = self.settings.trajectory_preview;
+        let assist = self.settings.assist_level.index();
+        let settings = self.ui(Screen::Settings);
+        settings.add(Widget::label("settings_title", &settings_title, Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 20, 300, 60)));
+        settings.add(Widget::toggle("settings_preview", "", preview, Layout::new(Anchor::Top, 0, 95, 300, 36)));
+        settings.add(Widget::slider("settings_assist", "", 0, AssistLevel::ALL.len() as i32 - 1, assist, Layout::new(Anchor::Top, 0, 140, 300, 40)));
+        settings.add(Widget::list("settings_bindings", Vec::new(), 6, Layout::new(Anchor::Top, 0, 195, 300, 240)));
+        settings.add(Widget::button("settings_language", "", Layout::new(Anchor::Top, 0, 445, 300, 36)));
+        for (name, text, x) in settings_buttons.iter() {
+            settings.add(Widget::button(name, text, Layout::new(Anchor::Top, *x, 490, 140, 36)));
+        }
+        settings.add(Widget::label("settings_message", "", Color::RGB(255, 120, 120), Layout::new(Anchor::Top, 0, 535, 400, 30)));
+        settings.add(Widget::button("settings_back", &back, Layout::new(Anchor::Bottom, 0, -50, 200, 60)));
+
+        let volumes = [self.settings.master_volume, self.settings.music_volume, self.settings.sfx_volume];
+        let muted = self.settings.muted;
+        let audio = self.ui(Screen::Audio);
+        audio.add(Widget::label("audio_title", &audio_title, Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 40, 300, 60)));
+        for (i, (name, volume)) in ["audio_master", "audio_music", "audio_sfx"].iter().zip(volumes).enumerate() {
+            audio.add(Widget::slider(name, "", 0, MAX_VOLUME, volume, Layout::new(Anchor::Top, 0, 150 + 60 * i as i32, 300, 40)));
+        }
+        audio.add(Widget::toggle("audio_mute", "", muted, Layout::new(Anchor::Top, 0, 330, 300, 36)));
+        audio.add(Widget::button("audio_back", &back, Layout::new(Anchor::Bottom, 0, -50, 200, 60)));
+        self.set_audio_labels();
+
+        let toggles = [
+            ("display_low_effects", self.settings.low_effects),
+            ("display_screen_shake", self.settings.screen_shake),
+            ("display_hit_stop", self.settings.hit_stop),
+            ("display_ball_trails", self.settings.ball_trails),
+            ("display_reduce_motion", self.settings.reduce_motion),
+            ("display_fullscreen", self.settings.fullscreen),
+            ("display_brick_patterns", self.settings.brick_patterns)
+        ];
+        let preset = self.settings.color_preset.index();
+        let display = self.ui(Screen::Display);
+        display.add(Widget::label("display_title", &display_title, Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 40, 300, 60)));
+        for (i, (name, checked)) in toggles.iter().enumerate() {
+            display.add(Widget::toggle(name, "", *checked, Layout::new(Anchor::Top, 0, 130 + 44 * i as i32, 300, 36)));
+        }
+        display.add(Widget::slider("display_colors", "", 0, ColorPreset::ALL.len() as i32 - 1, preset, Layout::new(Anchor::Top, 0, 438, 300, 40)));
+        display.add(Widget::button("display_theme", "", Layout::new(Anchor::Top, 0, 488, 300, 36)));
+        display.add(Widget::button("display_back", &back, Layout::new(Anchor::Bottom, 0, -50, 200, 60)));
+        self.set_display_labels();
+
+        for (screen, back_name, title, lines) in secondary_screens {
+            let ui = self.ui(screen);
+            ui.add(Widget::label("title", &title, Color::RGB(255, 255, 255), Layout::new(Anchor::Top, 0, 40, 400, 70)));
+            for (i, line) in lines.iter().enumerate() {
+                ui.add(Widget::label(&format!("{}{}", "line_", i), line, Color::RGB(200, 200, 200), Layout::new(Anchor::Top, 0, 180 + 60 * i as i32, 450, 40)));
+            }
+            ui.add(Widget::button(back_name, &back, Layout::new(Anchor::Bottom, 0, -50, 200, 100)));
+        }
+        self.set_decorations();
+        self.set_settings_labels();
+    }
 
+    // Walls and limit bar, in the theme's colors.
+    fn set_decorations(&mut self) {
+        let theme = &self.skin.theme;
         let left_bar_outside = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("left_bar").unwrap()),
             rect: rect!(100, 75, 5, 600),
-            color: Color::RGB(50, 50, 255)
+            color: theme.wall
         };
 
         let right_bar_outside = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("right_bar").unwrap()),
             rect: rect!(WINDOW_WIDTH-105, 75, 5, 600),
-            color: Color::RGB(50, 50, 255)
+            color: theme.wall
         };
         
         let left_bar_inside = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("left_bar").unwrap()),
             rect: rect!(101, 76, 3, 598),
-            color: Color::RGB(0, 0, 0)
+            color: theme.background
         };
 
         let right_bar_inside = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("right_bar").unwrap()),
             rect: rect!(WINDOW_WIDTH-104, 76, 3, 598),
-            color: Color::RGB(0, 0, 0)
+            color: theme.background
         };
 
         let top_bar_outside = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("top_bar").unwrap()),
             rect: rect!(100, 75, 400, 5),
-            color: Color::RGB(50, 50, 255)
+            color: theme.wall
         };
 
         let top_bar_inside = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("top_bar").unwrap()),
             rect: rect!(101, 76, 398, 3),
-            color: Color::RGB(0, 0, 0)
+            color: theme.background
         };
 
-        self.drawn.push(left_bar_outside);
-        self.drawn.push(right_bar_outside);
-        self.drawn.push(top_bar_outside);   
-        self.drawn.push(left_bar_inside);
-        self.drawn.push(right_bar_inside);
-        self.drawn.push(top_bar_inside);
-
         let limit_bar = DrawnContent {
-            displayed_in_game: true,
-            displayed_in_pause: false,
-            displayed_at_loss: false,
-            name: Some(String::from_str("limit_bar").unwrap()),
-            rect: rect!(101, 585, 398, 3),
-            color: Color::RGB(255, 0, 0)
+            rect: rect!(101, LIMIT_BAR_Y, 398, 3),
+            color: theme.limit_bar
         };
 
-        self.drawn.push(limit_bar);
+        self.decorations = vec![left_bar_outside, right_bar_outside, top_bar_outside, left_bar_inside, right_bar_inside, top_bar_inside, limit_bar];
+    }
 
-        let retry_button = DrawnContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: true,
-            name: Some(String::from_str("retry_button").unwrap()),
-            rect: rect!(200,  475, 200, 100),
-            color: Color::RGB(255, 255, 255)
-        };
+    pub(crate) fn set_settings_labels(&mut self) {
+        let preview = self.locale.format("settings-preview", &[("state", on_off(self.settings.trajectory_preview))]);
+        let assist = self.locale.format("settings-assist", &[("level", self.settings.assist_level.name())]);
+        let language = self.locale.get("settings-language");
+        let message = self.settings_message.clone().unwrap_or_default();
 
-        let retry_surface = font
-            .render("Retry")
-            .blended(Color::RGBA(0, 0, 0, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let retry_texture = texture_creator
-            .create_texture_from_surface(&retry_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let retry_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: true,
-            name: None,
-            texture: retry_texture,
-            src: None,
-            dst: Some(rect!(225, 500, 150, 50))
-        };
+        let mut bindings: Vec<String> = Vec::new();
+        for action in Action::ALL.iter() {
+            let keys = if self.rebinding == Some(*action) { self.locale.get("press-a-key") } else { self.settings.bindings.describe(*action) };
+            bindings.push(self.locale.format("binding", &[("action", &self.action_label(*action)), ("keys", &keys)]));
+        }
 
-        let loss_title_surface = font
-            .render("You lose!")
-            .blended(Color::RGBA(255, 255, 255, 255))
-            .map_err(|e| e.to_string()).unwrap();
-
-        let loss_title_texture = texture_creator
-            .create_texture_from_surface(&loss_title_surface)
-            .map_err(|e| e.to_string()).unwrap();
-
-        let loss_title_textured_content = TexturedContent {
-            displayed_in_game: false,
-            displayed_in_pause: false,
-            displayed_at_loss: true,
-            name: None,
-            texture: loss_title_texture,
-            src: None,
-            dst: Some(rect!(225, 200, 150, 50))
-        };
+        let settings = self.ui(Screen::Settings);
+        settings.set_text("settings_preview", &preview);
+        settings.set_text("settings_language", &language);
+        settings.set_text("settings_assist", &assist);
+        settings.set_items("settings_bindings", bindings);
+        settings.set_text("settings_message", &message);
+    }
 
-        self.drawn.push(retry_button);
-        self.textured.push(retry_textured_content);
-        self.textured.push(loss_title_textured_content);
+    pub(crate) fn set_audio_labels(&mut self) {
+        let percent = |id: &str, volume: i32| self.locale.format(id, &[("percent", &(volume * 100 / MAX_VOLUME).to_string())]);
+        let master = percent("audio-master", self.settings.master_volume);
+        let music = percent("audio-music", self.settings.music_volume);
+        let sfx = percent("audio-effects", self.settings.sfx_volume);
+        let muted = self.settings.muted;
+        let mute = self.locale.format("audio-mute", &[("state", on_off(muted))]);
+
+        let audio = self.ui(Screen::Audio);
+        audio.set_text("audio_master", &master);
+        audio.set_text("audio_music", &music);
+        audio.set_text("audio_sfx", &sfx);
+        audio.set_text("audio_mute", &mute);
+        audio.set_checked("audio_mute", muted);
+    }
 
+    pub(crate) fn set_display_labels(&mut self) {
+        let settings = &self.settings;
+        let labels = [
+            ("display_low_effects", "display-low-effects", settings.low_effects),
+            ("display_screen_shake", "display-screen-shake", settings.screen_shake),
+            ("display_hit_stop", "display-hit-stop", settings.hit_stop),
+            ("display_ball_trails", "display-ball-trails", settings.ball_trails),
+            ("display_reduce_motion", "display-reduce-motion", settings.reduce_motion),
+            ("display_fullscreen", "display-fullscreen", settings.fullscreen),
+            ("display_brick_patterns", "display-brick-patterns", settings.brick_patterns)
+        ].map(|(name, id, checked)| (name, checked, self.locale.format(id, &[("state", on_off(checked))])));
+        let colors = self.locale.format("display-colors", &[("preset", settings.color_preset.name())]);
+        let theme = self.locale.format("display-theme", &[("theme", &self.skin.theme.label)]);
+
+        let display = self.ui(Screen::Display);
+        for (name, checked, text) in labels {
+            display.set_text(name, &text);
+            display.set_checked(name, checked);
+        }
+        display.set_text("display_colors", &colors);
+        display.set_text("display_theme", &theme);
     }
 
-    pub(crate) fn display_menu(&self, mut can: Canvas<Window>) -> Canvas<Window> {
-        can.set_draw_color(Color::RGB(0, 0, 0));
-        can.clear();
-        for content in self.drawn.iter() {
-            if !content.displayed_in_game && !content.displayed_at_loss {
-                let _ = can.set_draw_color(content.color);
-                let _ = can.fill_rect(content.rect);
+    // The window follows the setting on the next frame.
+    pub(crate) fn toggle_fullscreen(&mut self) {
+        self.settings.fullscreen = !self.settings.fullscreen;
+        self.settings.save();
+        self.set_display_labels();
+    }
+
+    pub(crate) fn toggle_mute(&mut self) {
+        self.settings.muted = !self.settings.muted;
+        self.settings.save();
+        self.set_audio_labels();
+    }
+
+    pub(crate) fn rebind(&mut self, key: Keycode) {
+        if let Some(action) = self.rebinding.take() {
+            self.settings_message = None;
+
+            match self.settings.bindings.bind(action, key) {
+                Ok(()) => self.settings.save(),
+                Err(other) => self.settings_message = Some(self.locale.format("key-taken", &[("key", &key.name()), ("action", &self.action_label(other))]))
             }
+            self.set_settings_labels();
         }
-        
-        for content in self.textured.iter() {
-            if !content.displayed_in_game && !content.displayed_at_loss {
-                let _ = can.copy(&content.texture, content.src, content.dst);
+    }
+
+    pub(crate) fn update_rebinding(&mut self) {
+        if self.rebinding.is_some() && self.rebinding_since.elapsed() >= REBIND_TIMEOUT {
+            self.rebinding = None;
+            self.set_settings_labels();
+        }
+    }
+
+    // Widget texts are only rendered after a change.
+    pub(crate) fn refresh_ui(&mut self) {
+        for ui in self.uis.values_mut() {
+            if ui.needs_refresh() {
+                ui.refresh(&mut self.text);
             }
         }
-        can
     }
 
-    pub(crate) fn display_pause(&self, mut can: Canvas<Window>) -> Canvas<Window> {
-        can.set_draw_color(Color::RGB(0, 0, 0));
+    pub(crate) fn display_screen(&self, mut can: Canvas<Window>) -> Canvas<Window> {
+        can.set_draw_color(self.skin.theme.background);
         can.clear();
-
-        for content in self.drawn.iter() {
-            if content.displayed_in_pause && content.displayed_in_game {
-                let _ = can.set_draw_color(content.color);
-                let _ = can.fill_rect(content.rect);
-            }
+        if let Some(background) = &self.skin.background {
+            let _ = can.copy(background, None, None);
         }
-        
-        for content in self.textured.iter() {
-            if content.displayed_in_pause && content.displayed_in_game {
-                let _ = can.copy(&content.texture, content.src, content.dst);
-            }
+
+        if let Some(ui) = self.uis.get(&self.screen()) {
+            can = ui.display(can);
         }
         can
     }
 
-    pub(crate) fn display_loss(&self, mut can: Canvas<Window>) -> Canvas<Window> {
-        can.set_draw_color(Color::RGB(0, 0, 0));
-        can.clear();
+    pub(crate) fn notify(&mut self, id: &str, path: &Path) {
+        let message = self.locale.format(id, &[("path", &path.display().to_string())]);
+        self.notice = Some((self.text.render(&message, Color::RGB(255, 255, 255)), Instant::now()));
+    }
 
-        for content in self.drawn.iter() {
-            if content.displayed_at_loss {
-                let _ = can.set_draw_color(content.color);
-                let _ = can.fill_rect(content.rect);
-            }
-        }
-        
-        for content in self.textured.iter() {
-            if content.displayed_at_loss {
-                let _ = can.copy(&content.texture, content.src, content.dst);
+    // Drawn after the captures, so that screenshots and recordings do not show it.
+    pub(crate) fn display_notice(&self, mut can: Canvas<Window>) -> Canvas<Window> {
+        if let Some((texture, since)) = &self.notice {
+            if since.elapsed() < NOTICE_DURATION {
+                let area = rect!(0, WINDOW_HEIGHT - 30, WINDOW_WIDTH, 30);
+                can.set_draw_color(Color::RGB(0, 0, 0));
+                let _ = can.fill_rect(area);
+                let _ = can.copy(texture, None, fit_text(texture, rect!(10, WINDOW_HEIGHT - 26, WINDOW_WIDTH - 20, 22)));
             }
         }
         can
     }
 
-    pub(crate) fn display_game(&self, mut can: Canvas<Window>, frame: i32, ) -> Canvas<Window> {
-        can.set_draw_color(Color::RGB(0, 0, 0));
-        can.clear();
+    pub(crate) fn display_game(&self, mut can: Canvas<Window>) -> Canvas<Window> {
+        can = self.display_screen(can);
 
-        for content in self.drawn.iter() {
-            if !content.displayed_in_pause && content.displayed_in_game {
-                let _ = can.set_draw_color(content.color);
-                let _ = can.fill_rect(content.rect);
-            }
-        }
-        
-        for content in self.textured.iter() {
-            if !content.displayed_in_pause && content.displayed_in_game {
-                let _ = can.copy(&content.texture, content.src, content.dst);
-            }
+        for decoration in self.decorations.iter() {
+            can.set_draw_color(decoration.color);
+            let _ = can.fill_rect(decoration.rect);
         }
 
-        can.copy(&self.damage_texture, None, rect!(0, 500, 100, 35)).unwrap();
-        can.copy(&self.balls_texture, None, rect!(0, 540, 100, 35)).unwrap();
-        can.copy(&self.size_texture, None, rect!(0, 580, 100, 35)).unwrap();
-
+        // Falling bricks only appear inside the playfield.
+        can.set_clip_rect(rect!(105, 80, 390, LIMIT_BAR_Y - 80));
         for brick in self.wave.bricks.iter() {
-            let mut brick_color = Color::RGB(255,255,255);
-
-            if brick.brick_type.eq(&String::from_str("bomb").unwrap()) {
-                brick_color = Color::RGB(200, 50, 50);
-            }
-            else if brick.brick_type.eq(&String::from_str("more_balls").unwrap()) {
-                brick_color = Color::RGB(50, 200, 50);
+            let rect = brick.display_rect();
+            let color = self.settings.color_preset.brick_color(&self.skin.theme, &brick.brick_type);
+            match self.skin.brick(&brick.brick_type) {
+                Some(sprite) => {
+                    let _ = can.copy(sprite, None, rect);
+                },
+                None => {
+                    can.set_draw_color(color);
+                    let _ = can.fill_rect(rect);
+                }
             }
-            else if brick.brick_type.eq(&String::from_str("more_damage").unwrap()) {
-                brick_color = Color::RGB(50, 50, 200);
+            if self.settings.brick_patterns {
+                can = Game::draw_pattern(can, &brick.brick_type, rect, color);
             }
-            else if brick.brick_type.eq(&String::from_str("bigger_balls").unwrap()) {
-                brick_color = Color::RGB(150, 150, 150);
-            };
-            can.set_draw_color(brick_color);
-            let _ = can.fill_rect(brick.rect);
-            let _ = can.copy(&brick.texture,None,brick.rect);
+            can = self.text.draw_number(can, brick.life, rect, color);
         }
+        can.set_clip_rect(None);
 
-        can = self.wave.display(can, frame);
+        can = self.wave.display(can);
 
         if self.display_bonus {
-            can.copy(&self.bonus_displayed, None, Rect::new(350, 600, 200, 40)).unwrap();
+            let _ = can.copy(&self.bonus_displayed, None, Rect::new(350, 600, 200, 40));
         }
 
         can
     }
 
-    pub(crate) fn act_drawn(&mut self, x: i32, y: i32, home_music_chunk: &Chunk, background_ig_music_chunk: &Chunk) {
-        for content in self.drawn.iter_mut() {
-            if (content.rect.x() <= x) && (x <= content.rect.x() + content.rect.width() as i32) && (content.rect.y() <= y) && (y <= content.rect.y() + content.rect.height() as i32) {
-                if content.name == Some(String::from_str("menu_start").unwrap()) && self.started == false && self.game_is_lost == false {
-                    self.started = true;
+    // One pattern per special brick kind, to tell them apart without color: a diamond
+    // for the bomb, dots for balls, stripes for damage, a frame for size.
+    // It stays on the edges so as not to hide the hit points.
+    fn draw_pattern(mut can: Canvas<Window>, kind: &str, rect: Rect, background: Color) -> Canvas<Window> {
+        let ink = Text::contrast_color(background);
+        can.set_blend_mode(BlendMode::Blend);
+        can.set_draw_color(Color::RGBA(ink.r, ink.g, ink.b, 170));
+
+        let (x, y, w, h) = (rect.x(), rect.y(), rect.width() as i32, rect.height() as i32);
+        match kind {
+            "bomb" => {
+                let corners = [(x + w / 2, y + 1), (x + w - 2, y + h / 2), (x + w / 2, y + h - 2), (x + 1, y + h / 2), (x + w / 2, y + 1)];
+                let points: Vec<Point> = corners.iter().map(|(px, py)| Point::new(*px, *py)).collect();
+                let _ = can.draw_lines(points.as_slice());
+            },
+            "more_balls" => {
+                for (dx, dy) in [(2, 2), (w - 5, 2), (2, h - 5), (w - 5, h - 5)] {
+                    let _ = can.fill_rect(rect!(x + dx, y + dy, 3, 3));
+                }
+            },
+            "more_damage" => {
+                for dy in [2, 5, h - 4, h - 7] {
+                    let _ = can.fill_rect(rect!(x + 2, y + dy, w - 4, 2));
+                }
+            },
+            "bigger_balls" => {
+                let _ = can.draw_rect(rect!(x + 2, y + 2, w - 4, h - 4));
+                let _ = can.draw_rect(rect!(x + 3, y + 3, w - 6, h - 6));
+            },
+            _ => {}
+        }
+        can.set_blend_mode(BlendMode::None);
+        can
+    }
+
+    pub(crate) fn pointer_moved(&mut self, x: i32, y: i32) {
+        let screen = self.screen();
+        self.ui(screen).pointer_moved(x, y);
+    }
+
+    // Returns true if the click lands on a widget, so that aiming does not start.
+    pub(crate) fn pointer_pressed(&mut self, x: i32, y: i32) -> bool {
+        let screen = self.screen();
+        self.ui(screen).pointer_pressed(x, y)
+    }
+
+    pub(crate) fn pointer_released(&mut self, x: i32, y: i32) {
+        let screen = self.screen();
+        if let Some(event) = self.ui(screen).pointer_released(x, y) {
+            self.handle_ui_event(event);
+        }
+    }
 
-                    sdl2::mixer::Channel(0).halt();
-                    sdl2::mixer::Channel(1).play(background_ig_music_chunk, 10000).unwrap();
+    pub(crate) fn scroll(&mut self, x: i32, y: i32, delta: i32) {
+        let screen = self.screen();
+        self.ui(screen).scroll(x, y, delta);
+    }
 
-                    self.balls = Vec::new();
-                    self.game_is_loaded = false;
+    pub(crate) fn move_focus(&mut self, step: i32) {
+        let screen = self.screen();
+        self.ui(screen).move_focus(step);
+    }
+
+    pub(crate) fn adjust(&mut self, step: i32) {
+        let screen = self.screen();
+        if let Some(event) = self.ui(screen).adjust(step) {
+            self.handle_ui_event(event);
+        }
+    }
+
+    pub(crate) fn act_focused(&mut self) {
+        let screen = self.screen();
+        if let Some(event) = self.ui(screen).activate() {
+            self.handle_ui_event(event);
+        }
+    }
+
+    fn handle_ui_event(&mut self, event: UiEvent) {
+        match event {
+            UiEvent::Toggled(name, on) if name == "settings_preview" => {
+                self.settings.trajectory_preview = on;
+                self.settings.save();
+                self.set_settings_labels();
+            },
+            UiEvent::Changed(name, value) if name == "settings_assist" => {
+                self.settings.assist_level = AssistLevel::ALL[value as usize];
+                self.settings.save();
+                self.set_settings_labels();
+            },
+            UiEvent::Changed(name, value) if name == "display_colors" => {
+                self.settings.color_preset = ColorPreset::ALL[value as usize];
+                self.settings.save();
+                self.set_display_labels();
+            },
+            UiEvent::Changed(name, value) if name.starts_with("audio_") => {
+                match name.as_str() {
+                    "audio_master" => self.settings.master_volume = value,
+                    "audio_music" => self.settings.music_volume = value,
+                    _ => self.settings.sfx_volume = value
                 }
-                if content.name == Some(String::from_str("pause_button").unwrap()) && self.paused == false && self.game_is_lost == false {
-                    self.paused = true;
-                    sdl2::mixer::Channel(1).pause();
+                self.settings.save();
+                self.set_audio_labels();
+            },
+            UiEvent::Toggled(name, on) if name == "audio_mute" => {
+                self.settings.muted = on;
+                self.settings.save();
+                self.set_audio_labels();
+            },
+            UiEvent::Toggled(name, on) if name == "display_low_effects" => {
+                self.settings.low_effects = on;
+                self.particles.low_effects = on;
+                self.settings.save();
+                self.set_display_labels();
+            },
+            UiEvent::Toggled(name, on) if name.starts_with("display_") => {
+                match name.as_str() {
+                    "display_screen_shake" => self.settings.screen_shake = on,
+                    "display_hit_stop" => self.settings.hit_stop = on,
+                    "display_ball_trails" => self.settings.ball_trails = on,
+                    "display_fullscreen" => self.settings.fullscreen = on,
+                    "display_brick_patterns" => self.settings.brick_patterns = on,
+                    _ => self.settings.reduce_motion = on
                 }
-                if content.name == Some(String::from_str("pause_resume").unwrap()) && self.paused == true {
-                    self.paused = false;
-                    sdl2::mixer::Channel(1).resume();
+                if !self.settings.shake_enabled() {
+                    self.camera.stop();
                 }
-                if content.name == Some(String::from_str("pause_giveup").unwrap()) && (self.started == true && self.paused == true) {
-                    self.started = false;
-                    self.paused = false;
+                self.settings.save();
+                self.set_display_labels();
+            },
+            UiEvent::Selected(name, row) if name == "settings_bindings" => {
+                self.rebinding = Action::ALL.get(row).copied();
+                self.rebinding_since = Instant::now();
+                self.settings_message = None;
+                self.set_settings_labels();
+            },
+            UiEvent::Clicked(name) => self.act_button(&name),
+            _ => {}
+        }
+    }
 
-                    sdl2::mixer::Channel(1).halt();
-                    sdl2::mixer::Channel(0).play(home_music_chunk, 2).unwrap();
-                }
-                if content.name == Some(String::from_str("retry_button").unwrap()) && (self.started == false && self.paused == false && self.game_is_lost == true) {
-                    self.game_is_lost = false;
-                    self.started = true;
-                    self.game_is_loaded = false;
+    pub(crate) fn act_button(&mut self, name: &str) {
+        let screen = self.screen();
+
+        match name {
+            "menu_start" if screen == Screen::Menu => self.set_screen(Screen::Playing),
+            "menu_settings" if screen == Screen::Menu => self.push_screen(Screen::Settings),
+            "menu_credits" if screen == Screen::Menu => self.push_screen(Screen::Credits),
+            "menu_shop" if screen == Screen::Menu => self.push_screen(Screen::Shop),
+            "menu_editor" if screen == Screen::Menu => self.push_screen(Screen::Editor),
+            "pause_button" if screen == Screen::Playing => self.push_screen(Screen::Paused),
+            "pause_resume" if screen == Screen::Paused => self.pop_screen(),
+            "pause_giveup" if screen == Screen::Paused => self.set_screen(Screen::Menu),
+            "recall_button" if screen == Screen::Playing => self.recall(),
+            "speed_button" if screen == Screen::Playing => self.toggle_fast_forward(),
+            "retry_button" if screen == Screen::Lost => self.set_screen(Screen::Playing),
+            "settings_audio" if screen == Screen::Settings => self.push_screen(Screen::Audio),
+            "settings_display" if screen == Screen::Settings => self.push_screen(Screen::Display),
+            "display_theme" if screen == Screen::Display => {
+                let current = self.themes.iter().position(|theme| *theme == self.skin.theme.name);
+                let next = current.map_or(0, |i| (i + 1) % self.themes.len());
+                let name = self.themes[next].clone();
+                self.set_theme(&name);
+            },
+            "settings_language" if screen == Screen::Settings => {
+                let current = self.languages.iter().position(|language| *language == self.locale.language);
+                let next = current.map_or(0, |i| (i + 1) % self.languages.len());
+                let language = self.languages[next].clone();
+                self.set_language(&language);
+            },
+            "settings_back" | "audio_back" | "display_back" | "credits_back" | "shop_back" | "editor_back" => self.pop_screen(),
+            "settings_reset" if screen == Screen::Settings => {
+                self.rebinding = None;
+                let (question, reset, cancel) = (self.locale.get("reset-confirm"), self.locale.get("reset"), self.locale.get("cancel"));
+                self.ui(Screen::Settings).open_dialog(&question, &[("reset_confirm", &reset), ("reset_cancel", &cancel)]);
+            },
+            "reset_confirm" if screen == Screen::Settings => {
+                self.settings.bindings = Bindings::defaults();
+                self.settings.save();
+                self.settings_message = Some(self.locale.get("keys-reset"));
+                self.set_settings_labels();
+            },
+            _ => {}
+        }
+    }
 
-                    sdl2::mixer::Channel(1).play(background_ig_music_chunk, 10000).unwrap();
-                }
+    // Goes back to the previous screen; returns false from the main menu to quit.
+    pub(crate) fn back(&mut self) -> bool {
+        let screen = self.screen();
+        if self.ui(screen).has_dialog() {
+            self.ui(screen).close_dialog();
+            return true;
+        }
+
+        match screen {
+            Screen::Menu => return false,
+            Screen::Playing => self.push_screen(Screen::Paused),
+            Screen::Lost => self.set_screen(Screen::Menu),
+            _ => self.pop_screen()
+        }
+        true
+    }
+
+    pub(crate) fn is_in_menu(&self) -> bool {
+        self.screen() != Screen::Playing
+    }
+    pub(crate) fn is_aiming(&self) -> bool {
+        self.screen() == Screen::Playing && !self.round && !self.is_animating()
+    }
+
+    // Launches the round and records it in the game's replay.
+    pub(crate) fn launch(&mut self) {
+        if self.is_aiming() {
+            self.round = true;
+            self.round_start_tick = self.tick;
+            self.replay.launches.push(Launch { tick: self.tick, angle: self.angle.value() });
+        }
+    }
+
+    // Ends the round on the next step: the balls in flight are removed and no more are launched.
+    pub(crate) fn recall(&mut self) {
+        if self.screen() == Screen::Playing && self.round && !self.balls.is_empty() {
+            self.replay.recalls.push(self.tick);
+            self.balls.clear();
+            self.balls_in_round = self.max_balls;
+        }
+    }
+
+    pub(crate) fn toggle_fast_forward(&mut self) {
+        self.fast_forward = !self.fast_forward;
+        self.update_hud();
+    }
+
+    // Only changes how many steps run between two frames, never the steps themselves.
+    pub(crate) fn steps_per_frame(&self) -> u32 {
+        if !self.round {
+            return 1;
+        }
+        let auto = 1 << ((self.tick - self.round_start_tick) / AUTO_SPEED_UP_TICKS).min(3);
+        let steps = if self.fast_forward { auto.max(FAST_FORWARD_STEPS) } else { auto };
+        steps.min(MAX_STEPS_PER_FRAME)
+    }
+
+    pub(crate) fn save_replay(&self) {
+        if let Err(e) = self.replay.save(Path::new(LAST_REPLAY_PATH)) {
+            error::log(&format!("cannot save replay {}: {}", LAST_REPLAY_PATH, e));
+        }
+    }
+
+    pub(crate) fn launched_ball(&self) -> Ball {
+        Ball::new(
+            (WINDOW_WIDTH - (self.ball_size as u32)) as f32 / 2.0,
+            (WINDOW_HEIGHT - (self.ball_size as u32)) as f32,
+            (self.angle.cos() as f32)*(unsafe { sqrtf((WINDOW_HEIGHT/10) as f32) }),
+            -(self.angle.sin() as f32)*(unsafe { sqrtf((WINDOW_WIDTH/10) as f32) }),
+        )
+    }
+
+    // Replays the first ball's path with the same physics as the simulation,
+    // without touching the bricks, and returns one point every PREVIEW_DOT_SPACING steps.
+    pub(crate) fn trajectory_preview(&self) -> Vec<Point> {
+        let mut ball = self.launched_ball();
+        let mut dots: Vec<Point> = Vec::new();
+        let mut brick_contacts = 0;
+        let level = self.settings.assist_level;
+
+        for step in 0..PREVIEW_MAX_STEPS {
+            match ball.advance(&self.wave.bricks, self.ball_size) {
+                Contact::Out => break,
+                Contact::Wall => {
+                    if !level.wall_bounces() {
+                        break;
+                    }
+                },
+                Contact::Brick(_) => {
+                    brick_contacts += 1;
+                    if brick_contacts >= level.brick_contacts() {
+                        dots.push(ball.center(self.ball_size));
+                        break;
+                    }
+                },
+                Contact::None => {}
+            }
+
+            if step % PREVIEW_DOT_SPACING == 0 {
+                dots.push(ball.center(self.ball_size));
             }
         }
+        dots
     }
 
-    pub(crate) fn update_balls_state(&mut self, frame: i32, ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>, new_ball_chunk: &Chunk, bricks_down_chunk: &Chunk, new_wave_chunk: &Chunk) {
-        if (self.round && self.balls_in_round < self.max_balls && frame % 2 == 0) || (self.round && self.balls_in_round == 0) {
-            self.balls.push(Ball::new(
-                (WINDOW_WIDTH - (self.ball_size as u32)) as f32 / 2.0,
-                (WINDOW_HEIGHT - (self.ball_size as u32)) as f32,
-                (self.angle.cos() as f32)*(unsafe { sqrtf((WINDOW_HEIGHT/10) as f32) }),
-                -(self.angle.sin() as f32)*(unsafe { sqrtf((WINDOW_WIDTH/10) as f32) }),
-            ));
+    // One call per game frame; the tick only counts simulated frames, so that
+    // replays depend neither on pauses nor on menus.
+    // During a hit-stop, the simulation and the tick are suspended together.
+    pub(crate) fn update_balls_state(&mut self) {
+        if self.hit_stop > 0 {
+            self.hit_stop -= 1;
+            return;
+        }
+        self.tick += 1;
+        self.update_animations();
+        if (self.round && self.balls_in_round < self.max_balls && self.tick.is_multiple_of(2)) || (self.round && self.balls_in_round == 0) {
+            let ball = self.launched_ball();
+            self.balls.push(ball);
             self.balls_in_round += 1;
-            sdl2::mixer::Channel(3).play(new_ball_chunk, 0).unwrap();
+            self.sounds.push(AudioEvent::Play(Cue::BallLaunch));
         }        
 
         for i in 0..self.balls.len() {
-            if self.balls[i].collision(&ttf_context, &texture_creator, &mut self.wave.bricks, new_ball_chunk, self.ball_damage, self.ball_size) == -1 {
-                self.index.push(i);
+            self.balls[i].remember_position();
+            match self.balls[i].collision(&mut self.wave.bricks, self.ball_damage, self.ball_size) {
+                Contact::Out => self.index.push(i),
+                Contact::Brick(j) => {
+                    // Only the hit points the brick still had count.
+                    self.score.damage((self.ball_damage + self.wave.bricks[j].life.min(0)).max(0) as u32);
+                    self.particles.emit(&SPARKS, self.balls[i].center(self.ball_size), Color::RGB(255, 230, 150));
+                    self.sounds.push(AudioEvent::Play(Cue::BrickHit));
+                },
+                _ => {}
             }
         }
         
@@ -780,249 +1163,305 @@ impl<'a> Game<'a> {
             self.balls.remove(*i);
         }
         self.index.clear();
+        if self.round {
+            self.break_bricks();
+        }
      
         if self.balls.is_empty() && self.round == true {
             self.round = false;
             self.balls_in_round = 0;
+            self.score.round_over();
             if self.wave.bricks.is_empty() {
+                self.score.wave_cleared(self.wave.wave_number);
                 self.wave.wave_number += 1;
-
-                let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-                let title_surface2 = font
-                    .render(&format!("{}{}", "Wave n°", self.wave.wave_number.to_string()))
-                    .blended(Color::RGBA(180, 120, 120, 255))
-                    .map_err(|e| e.to_string()).unwrap();
-
-                let title_texture2 = texture_creator
-                    .create_texture_from_surface(&title_surface2)
-                    .map_err(|e| e.to_string()).unwrap();
-                self.wave.title_texture = title_texture2;
-                
-                self.wave.load_bricks(ttf_context, texture_creator);
-                sdl2::mixer::Channel(5).play(new_wave_chunk, 0).unwrap();
+                self.set_wave_title();
+                self.wave.load_bricks();
+                self.sounds.push(AudioEvent::Play(Cue::WaveStart));
             }
             else {
                 self.get_bricks_down();
-                self.game_is_lost = self.is_lost(ttf_context, &texture_creator);
-                sdl2::mixer::Channel(4).play(bricks_down_chunk, 0).unwrap();
+                if self.is_in_danger() {
+                    self.shake(DANGER_SHAKE);
+                }
+                if self.is_lost() {
+                    self.set_screen(Screen::Lost);
+                }
+                self.sounds.push(AudioEvent::Play(Cue::BricksDown));
             }
         } 
 
+        let danger = self.is_in_danger();
+        if danger != self.danger {
+            self.danger = danger;
+            self.sounds.push(AudioEvent::Intensity(danger));
+        }
+
         if self.bonus_display_start_time.elapsed() > Duration::from_secs(2) {
             self.display_bonus = false;
         }
     }
 
-    pub(crate) fn display_balls_and_bricks(&mut self, mut canvas: Canvas<Window>, ball_texture: &Texture<'_>, frame: i32, ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>, broken_brick_chunk: &Chunk, more_bullets_chunk: &Chunk, more_damage_chunk: &Chunk, bigger_balls_chunk: &Chunk, brick_exploding_chunk: &Chunk) -> Canvas<Window> {
+    // Removes the bricks with no hit points left and those caught in a bomb blast. Each
+    // brick is scored once, by kind; only the bricks broken by damage apply their effect.
+    fn break_bricks(&mut self) {
+        let (broken, blasted) = destroyed_bricks(&self.wave.bricks, (BRICK_SIZE * 3) as i32);
+        if broken.is_empty() {
+            return;
+        }
+
+        for &i in broken.iter() {
+            let brick = &self.wave.bricks[i];
+
+            if brick.brick_type == "bomb" {
+                self.bonus_display_start_time = Instant::now();
+                self.display_bonus = true;
+                self.bonus_displayed = self.text.render(&self.locale.get("bonus-boom"), Color::RGBA(255, 255, 255, 255));
+
+                self.particles.shockwave(brick.rect.center(), BRICK_SIZE * 3);
+                if self.settings.shake_enabled() {
+                    self.camera.shake(BOMB_SHAKE);
+                }
+                self.sounds.push(AudioEvent::Play(Cue::Explosion));
+            }
+            else if brick.brick_type == "more_balls" {
+                self.bonus_display_start_time = Instant::now();
+                self.display_bonus = true;
+                self.bonus_displayed = self.text.render(&self.locale.format("bonus-balls", &[("count", "3")]), Color::RGBA(255, 255, 255, 255));
+
+                self.max_balls += 3;
+
+                self.hud_counters[0].retarget(self.max_balls as f32, COUNTER_FRAMES, Ease::OutCubic);
+
+                self.sounds.push(AudioEvent::Play(Cue::BonusBalls));
+            }
+            else if brick.brick_type == "more_damage" {
+                self.bonus_display_start_time = Instant::now();
+                self.display_bonus = true;
+                self.bonus_displayed = self.text.render(&self.locale.get("bonus-damage"), Color::RGBA(255, 255, 255, 255));
+
+                self.ball_damage += 3;
+
+                self.hud_counters[1].retarget(self.ball_damage as f32, COUNTER_FRAMES, Ease::OutCubic);
+
+                self.sounds.push(AudioEvent::Play(Cue::BonusDamage));
+            }
+            else if brick.brick_type == "bigger_balls" {
+                self.bonus_display_start_time = Instant::now();
+                self.display_bonus = true;
+                self.bonus_displayed = self.text.render(&self.locale.get("bonus-size"), Color::RGBA(255, 255, 255, 255));
+
+                self.ball_size += 3;
+
+                self.hud_counters[2].retarget(self.ball_size as f32, COUNTER_FRAMES, Ease::OutCubic);
+
+                self.sounds.push(AudioEvent::Play(Cue::BonusSize));
+            };
+            if self.settings.hit_stop_enabled() {
+                self.hit_stop = self.hit_stop.max(hit_stop_for(brick.max_life, self.ball_damage));
+            }
+            self.score.brick_broken(brick.brick_type != "normal");
+        }
+
+        for &i in broken.iter().chain(blasted.iter()) {
+            let brick = &self.wave.bricks[i];
+            let color = self.settings.color_preset.brick_color(&self.skin.theme, &brick.brick_type);
+            self.particles.emit(&DEBRIS, brick.rect.center(), color);
+        }
+        for &i in blasted.iter() {
+            self.score.brick_broken(self.wave.bricks[i].brick_type != "normal");
+        }
+        self.coins += (broken.len() + blasted.len()) as u32;
+        self.sounds.push(AudioEvent::Play(Cue::BrickBroken));
+
+        let mut i = 0;
+        self.wave.bricks.retain(|_| {
+            let kept = !broken.contains(&i) && !blasted.contains(&i);
+            i += 1;
+            kept
+        });
+    }
+
+    pub(crate) fn display_balls_and_bricks(&mut self, mut canvas: Canvas<Window>) -> Canvas<Window> {
         canvas.set_draw_color(Color::RGB(255, 255, 255));
         
-        canvas = self.display_game(canvas, frame);
+        // The whole playfield follows the camera; the viewport is restored at the end of the frame.
+        self.camera.update();
+        canvas.set_viewport(self.camera.viewport());
+        canvas = self.display_game(canvas);
         
-        if !self.round {
-            canvas.draw_line(
+        if !self.round && self.settings.trajectory_preview {
+            canvas.set_draw_color(Color::RGB(255, 255, 255));
+            for dot in self.trajectory_preview() {
+                let _ = canvas.fill_rect(rect!(dot.x - 1, dot.y - 1, 3, 3));
+            }
+        }
+        else if !self.round {
+            let _ = canvas.draw_line(
                 (WINDOW_WIDTH as i32 / 2, WINDOW_HEIGHT as i32),
                 (
                     (WINDOW_WIDTH as f64 / 2.0 + 200.0 * self.angle.cos()) as i32,
                     (WINDOW_HEIGHT as f64 - 200.0 * self.angle.sin()) as i32,
                 ),
-            ).unwrap();
+            );
         }
 
         if self.round {
-            for i in 0..self.wave.bricks.len() {
-                if self.wave.bricks[i].life <= 0 {
-                    self.index.push(i);
+            let ball_texture = &mut self.skin.ball;
+            // Past positions are drawn increasingly opaque, under the balls.
+            if self.settings.trails_enabled() {
+                for ball in &(self.balls) {
+                    let trail = ball.trail(self.ball_size);
+                    for (k, rect) in trail.iter().enumerate() {
+                        ball_texture.set_alpha_mod((TRAIL_ALPHA as usize * (k + 1) / (trail.len() + 1)) as u8);
+                        let _ = canvas.copy(ball_texture, None, *rect);
+                    }
                 }
+                ball_texture.set_alpha_mod(255);
             }
 
-            for i in self.index.iter_mut().rev() {
-                let mut bricks_to_remove: Vec<Rect> = Vec::new();
+            for ball in &(self.balls) {
+                let _ = canvas.copy(ball_texture, None, ball.rect(self.ball_size));
+            }
+        }
 
-                let brick = self.wave.bricks.get(*i).unwrap();
+        self.particles.update();
+        canvas = self.particles.display(canvas);
+        canvas.set_viewport(None);
 
-                if brick.brick_type.eq(&String::from_str("bomb").unwrap()) {
-                    self.bonus_display_start_time = Instant::now();
-                    self.display_bonus = true;
+        canvas
+    }
 
-                    let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-                    let bonus_surface = font
-                        .render("Boom!")
-                        .blended(Color::RGBA(255, 255, 255, 255))
-                        .map_err(|e| e.to_string()).unwrap();
+    fn update_animations(&mut self) {
+        for brick in self.wave.bricks.iter_mut() {
+            brick.offset.update();
+        }
+        self.wave.title_offset.update();
+        for counter in self.hud_counters.iter_mut() {
+            counter.update();
+        }
+        self.update_hud();
+    }
 
-                    let bonus_texture = texture_creator
-                        .create_texture_from_surface(&bonus_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-                    self.bonus_displayed = bonus_texture;
+    // Counters are only rendered again when the displayed value changes.
+    fn update_hud(&mut self) {
+        let [max_balls, damage, size] = self.hud_counters.map(|counter| counter.value().round() as i32);
+        let values = [
+            self.score.points as i64,
+            self.score.multiplier() as i64,
+            self.wave.wave_number as i64,
+            self.wave.bricks.len() as i64,
+            self.balls.len() as i64,
+            max_balls as i64,
+            self.coins as i64,
+            damage as i64,
+            size as i64,
+            self.rows_left().map_or(-1, |rows| rows as i64),
+            self.is_in_danger() as i64,
+            self.steps_per_frame() as i64
+        ];
+        if self.hud_values == Some(values) {
+            return;
+        }
+        self.hud_values = Some(values);
+
+        let count = |id: &str, value: String| self.locale.format(id, &[("count", &value)]);
+        let texts = [
+            ("hud_score_title", self.locale.get("hud-score")),
+            ("hud_score", self.score.points.to_string()),
+            ("hud_combo", match self.score.multiplier() {
+                1 => String::new(),
+                multiplier => self.locale.format("hud-combo", &[("multiplier", &multiplier.to_string())])
+            }),
+            ("hud_wave", self.locale.format("hud-wave", &[("wave", &self.wave.wave_number.to_string())])),
+            ("hud_bricks", count("hud-bricks", self.wave.bricks.len().to_string())),
+            ("hud_balls", self.locale.format("hud-balls", &[("count", &self.balls.len().to_string()), ("max", &max_balls.to_string())])),
+            ("hud_coins", count("hud-coins", self.coins.to_string())),
+            ("hud_powerups", self.locale.get("hud-powerups")),
+            ("hud_damage", count("hud-damage", damage.to_string())),
+            ("hud_size", count("hud-size", size.to_string())),
+            ("hud_rows", self.rows_left().map_or(String::new(), |rows| count("hud-rows", rows.to_string())))
+        ];
+        let speed = self.locale.format("hud-speed", &[("speed", &self.steps_per_frame().to_string())]);
+        let (normal, alert) = (self.skin.theme.hud, self.skin.theme.limit_bar);
+        let danger = self.is_in_danger();
+
+        let playing = self.ui(Screen::Playing);
+        playing.set_text("speed_button", &speed);
+        for (name, text) in texts.iter() {
+            playing.set_text(name, text);
+            playing.set_color(name, if *name == "hud_rows" && danger { alert } else { normal });
+        }
+    }
 
-                    for j in 0..self.wave.bricks.len() {
-                        let distance = self.wave.bricks[j].euclidian_distance(brick);
-                        if !(self.wave.bricks[j].rect.eq(&brick.rect)) && distance <= (BRICK_SIZE * 3) as i32 {
-                            bricks_to_remove.push(self.wave.bricks[j].rect.clone());
-                        }
-                    }
-                    sdl2::mixer::Channel(7).play(brick_exploding_chunk, 0).unwrap();
-                }
-                else if brick.brick_type.eq(&String::from_str("more_balls").unwrap()) {
-                    self.bonus_display_start_time = Instant::now();
-                    self.display_bonus = true;
-
-                    let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-                    let bonus_surface = font
-                        .render("More bullets!")
-                        .blended(Color::RGBA(255, 255, 255, 255))
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    let bonus_texture = texture_creator
-                        .create_texture_from_surface(&bonus_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-                    self.bonus_displayed = bonus_texture;
-
-                    self.max_balls += 3;
-
-                    let balls_surface = font
-                        .render(&format!("{}{}", "Balls: ", self.max_balls.to_string()))
-                        .blended(Color::RGBA(200, 200, 200, 255))
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    let balls_texture = texture_creator
-                        .create_texture_from_surface(&balls_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-                    
-                    self.balls_texture = balls_texture;
-
-                    sdl2::mixer::Channel(7).play(more_bullets_chunk, 0).unwrap();
-                }
-                else if brick.brick_type.eq(&String::from_str("more_damage").unwrap()) {
-                    self.bonus_display_start_time = Instant::now();
-                    self.display_bonus = true;
-                    
-                    let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-                    let bonus_surface = font
-                        .render("More damage!")
-                        .blended(Color::RGBA(255, 255, 255, 255))
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    let bonus_texture = texture_creator
-                        .create_texture_from_surface(&bonus_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-                    self.bonus_displayed = bonus_texture;
-
-                    self.ball_damage += 3;
-
-                    let damage_surface = font
-                        .render(&format!("{}{}", "Dmg: ", self.ball_damage.to_string()))
-                        .blended(Color::RGBA(200, 200, 200, 255))
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    let damage_texture = texture_creator
-                        .create_texture_from_surface(&damage_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    self.damage_texture = damage_texture;
-
-                    sdl2::mixer::Channel(8).play(more_damage_chunk, 0).unwrap();
-                }
-                else if brick.brick_type.eq(&String::from_str("bigger_balls").unwrap()) {
-                    self.bonus_display_start_time = Instant::now();
-                    self.display_bonus = true;
-                    
-                    let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-                    let bonus_surface = font
-                        .render("Bigger balls!")
-                        .blended(Color::RGBA(255, 255, 255, 255))
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    let bonus_texture = texture_creator
-                        .create_texture_from_surface(&bonus_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-                    self.bonus_displayed = bonus_texture;
-
-                    self.ball_size += 3;
-
-                    let size_surface = font
-                        .render(&format!("{}{}{}", "Size: ", self.ball_size.to_string(), "px"))
-                        .blended(Color::RGBA(200, 200, 200, 255))
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    let size_texture = texture_creator
-                        .create_texture_from_surface(&size_surface)
-                        .map_err(|e| e.to_string()).unwrap();
-
-                    self.size_texture = size_texture;
-
-                    sdl2::mixer::Channel(9).play(bigger_balls_chunk, 0).unwrap();
-                };
-                self.wave.bricks.remove(*i);
-                self.wave.bricks.retain(|brick| !bricks_to_remove.contains(&brick.rect));
-                sdl2::mixer::Channel(2).play(broken_brick_chunk, 0).unwrap();
-            }
-            self.index.clear();
+    // Moving bricks block aiming until their animation is over.
+    pub(crate) fn is_animating(&self) -> bool {
+        self.wave.bricks.iter().any(|brick| !brick.offset.is_done())
+    }
 
-            for ball in &(self.balls) {
-                canvas.copy(&ball_texture, None, ball.rect(self.ball_size)).unwrap();
-            }
+    fn shake(&mut self, amount: f32) {
+        if self.settings.shake_enabled() {
+            self.camera.shake(amount);
         }
-
-        canvas
     }
 
     pub(crate) fn get_bricks_down(&mut self) {
         for brick in self.wave.bricks.iter_mut() {
             brick.rect.y += BRICK_SIZE as i32;
+            brick.offset = Tween::new(-(BRICK_SIZE as f32), 0.0, DESCENT_FRAMES, Ease::InOutQuad);
+        }
+    }
+
+    // Descents left before the lowest brick crosses the limit bar, none without bricks.
+    fn rows_left(&self) -> Option<i32> {
+        self.wave.bricks.iter()
+            .map(|brick| (LIMIT_BAR_Y - brick.rect.y - brick.rect.height() as i32).div_euclid(BRICK_SIZE as i32))
+            .min()
+    }
+
+    // True when a brick is two descents or less away from the limit bar.
+    fn is_in_danger(&self) -> bool {
+        self.rows_left().is_some_and(|rows| rows < DANGER_ROWS)
+    }
+
+    // Enters the game in the high scores and fills the end screen.
+    fn record_score(&mut self) {
+        let score = HighScore {
+            points: self.score.points,
+            wave: self.wave.wave_number,
+            best_combo: self.score.best_combo
+        };
+        let mode = self.score.rules.mode.clone();
+        let best = self.high_scores.best(&mode);
+        let rank = if self.record_scores { self.high_scores.record(&mode, score) } else { None };
+
+        let best_score = match (rank, best) {
+            (Some(0), _) => self.locale.get("new-record"),
+            (_, Some(best)) => self.locale.format("best-score", &[("best", &best.points.to_string())]),
+            (_, None) => String::new()
+        };
+        let texts = [
+            ("lost_score", self.locale.format("lost-score", &[("score", &score.points.to_string())])),
+            ("best_score", best_score),
+            ("lost_wave", self.locale.format("lost-wave", &[("wave", &score.wave.to_string())])),
+            ("lost_combo", self.locale.format("lost-combo", &[("count", &score.best_combo.to_string())]))
+        ];
+        let lost = self.ui(Screen::Lost);
+        for (name, text) in texts.iter() {
+            lost.set_text(name, text);
         }
     }
 
-    pub(crate) fn is_lost(&mut self, ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) -> bool {
+    pub(crate) fn is_lost(&mut self) -> bool {
         for brick in self.wave.bricks.iter() {
-            if brick.rect.y + brick.rect.height() as i32 > 585 {
-                self.started = false;
-                self.game_is_lost = true;
+            if brick.rect.y + brick.rect.height() as i32 > LIMIT_BAR_Y {
                 self.ball_size = 10;
                 self.max_balls = 10;
                 self.ball_damage = 5;
-                for score in 0..self.textured.len() {
-                    if self.textured[score].name.eq(&Some(String::from_str("best_score").unwrap())) {
-                        self.textured.remove(score);
-                    }
-                }
-
-                let font = ttf_context.load_font(Path::new("fonts/Marlboro.ttf"), 128).unwrap();
-
-                let loss_subtitle_surface = font
-                    .render(&format!("{}{}", "Best score: ", self.wave.wave_number.to_string()))
-                    .blended(Color::RGBA(255, 255, 255, 255))
-                    .map_err(|e| e.to_string()).unwrap();
-
-                let loss_subtitle_texture = texture_creator
-                    .create_texture_from_surface(&loss_subtitle_surface)
-                    .map_err(|e| e.to_string()).unwrap();
-
-                let loss_subtitle_textured_content = TexturedContent {
-                    displayed_in_game: false,
-                    displayed_in_pause: false,
-                    displayed_at_loss: true,
-                    name: Some(String::from_str("best_score").unwrap()),
-                    texture: loss_subtitle_texture,
-                    src: None,
-                    dst: Some(rect!(225, 300, 150, 40))
-                };
-
-                self.textured.push(loss_subtitle_textured_content);
-
+                self.record_score();
 
                 self.wave.wave_number = 1;
 
-                let title_surface2 = font
-                    .render(&format!("{}{}", "Wave n°", self.wave.wave_number.to_string()))
-                    .blended(Color::RGBA(180, 120, 120, 255))
-                    .map_err(|e| e.to_string()).unwrap();
-
-                let title_texture2 = texture_creator
-                    .create_texture_from_surface(&title_surface2)
-                    .map_err(|e| e.to_string()).unwrap();
-                self.wave.title_texture = title_texture2;
-                sdl2::mixer::Channel(1).halt();
+                self.set_wave_title();
                 
                 return true;
             }
@@ -1032,5 +1471,34 @@ impl<'a> Game<'a> {
     }
 }
 
+// Only the bricks that took many hits to break earn a hit-stop, so it fades out as the
+// balls get stronger.
+fn hit_stop_for(max_life: i32, damage: i32) -> u32 {
+    let damage = damage.max(1);
+    let hits = (max_life + damage - 1) / damage;
+    if hits >= HIT_STOP_HITS { HIT_STOP_TICKS } else { 0 }
+}
+
+// Selector key for `{ $state -> [on] ... *[off] ... }` in the catalogs.
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn one_hit_brick_does_not_stop() {
+        assert_eq!(hit_stop_for(5, 5), 0);
+        assert_eq!(hit_stop_for(4, 20), 0);
+    }
 
+    #[test]
+    fn brick_needing_many_hits_stops() {
+        assert_eq!(hit_stop_for(100, 5), HIT_STOP_TICKS);
+        assert_eq!(hit_stop_for(HIT_STOP_HITS * 8, 8), HIT_STOP_TICKS);
+        assert_eq!(hit_stop_for(HIT_STOP_HITS * 8 - 8, 8), 0);
+        assert_eq!(hit_stop_for(100, 0), HIT_STOP_TICKS);
+    }
+}
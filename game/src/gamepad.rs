@@ -0,0 +1,81 @@
+use sdl2::controller::{Axis, GameController};
+use sdl2::GameControllerSubsystem;
+use std::f64::consts::PI;
+
+use crate::utils::Angle;
+
+const STICK_DEAD_ZONE: i16 = 8000;
+const STICK_SPEED: f64 = PI / 60.0;
+const TRIGGER_SPEED: f64 = PI / 600.0;
+
+struct Pad {
+    controller: GameController,
+    stick_x: i16,
+    trigger_left: i16,
+    trigger_right: i16
+}
+
+// Hot-plugged controllers: SDL sends ControllerDeviceAdded for each controller
+// already present at startup, then on each plug. Without the controller subsystem,
+// the game is played with keyboard and mouse.
+pub struct Gamepads {
+    subsystem: Option<GameControllerSubsystem>,
+    pads: Vec<Pad>
+}
+
+impl Gamepads {
+    pub fn new(subsystem: Option<GameControllerSubsystem>) -> Self {
+        Gamepads {
+            subsystem,
+            pads: Vec::new()
+        }
+    }
+
+    pub fn add(&mut self, joystick_index: u32) {
+        let controller = match &self.subsystem {
+            Some(subsystem) => subsystem.open(joystick_index),
+            None => return
+        };
+
+        if let Ok(controller) = controller {
+            if self.pads.iter().all(|pad| pad.controller.instance_id() != controller.instance_id()) {
+                self.pads.push(Pad {
+                    controller,
+                    stick_x: 0,
+                    trigger_left: 0,
+                    trigger_right: 0
+                });
+            }
+        }
+    }
+
+    pub fn remove(&mut self, instance_id: u32) {
+        self.pads.retain(|pad| pad.controller.instance_id() != instance_id);
+    }
+
+    pub fn axis(&mut self, instance_id: u32, axis: Axis, value: i16) {
+        for pad in self.pads.iter_mut() {
+            if pad.controller.instance_id() == instance_id {
+                match axis {
+                    Axis::LeftX => pad.stick_x = value,
+                    Axis::TriggerLeft => pad.trigger_left = value,
+                    Axis::TriggerRight => pad.trigger_right = value,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Called every frame: the stick aims with a speed proportional to its
+    // tilt, the triggers allow fine adjustment.
+    pub fn aim(&self, angle: &mut Angle) {
+        for pad in self.pads.iter() {
+            if pad.stick_x.abs() > STICK_DEAD_ZONE {
+                angle.rotate(-(pad.stick_x as f64 / i16::MAX as f64) * STICK_SPEED);
+            }
+
+            let trigger = (pad.trigger_left as f64 - pad.trigger_right as f64) / i16::MAX as f64;
+            angle.rotate(trigger * TRIGGER_SPEED);
+        }
+    }
+}
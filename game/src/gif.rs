@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const CLEAR_CODE: u16 = 256;
+const END_CODE: u16 = 257;
+const MAX_CODE_SIZE: u32 = 12;
+
+// Minimal GIF89a encoder: 256-color global palette, full-frame indexed
+// images, looping animation.
+pub fn write(path: &Path, width: u16, height: u16, palette: &[[u8; 3]], frames: &[&[u8]], delay: u16) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    file.write_all(b"GIF89a")?;
+    file.write_all(&width.to_le_bytes())?;
+    file.write_all(&height.to_le_bytes())?;
+    // Global palette present, 8 bits per component, 256 entries.
+    file.write_all(&[0xF7, 0, 0])?;
+    for i in 0..256 {
+        file.write_all(palette.get(i).unwrap_or(&[0, 0, 0]))?;
+    }
+
+    file.write_all(&[0x21, 0xFF, 0x0B])?;
+    file.write_all(b"NETSCAPE2.0")?;
+    file.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+    for frame in frames.iter() {
+        file.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        file.write_all(&delay.to_le_bytes())?;
+        file.write_all(&[0x00, 0x00])?;
+
+        file.write_all(&[0x2C, 0, 0, 0, 0])?;
+        file.write_all(&width.to_le_bytes())?;
+        file.write_all(&height.to_le_bytes())?;
+        file.write_all(&[0x00, 0x08])?;
+        for block in compress(frame).chunks(255) {
+            file.write_all(&[block.len() as u8])?;
+            file.write_all(block)?;
+        }
+        file.write_all(&[0x00])?;
+    }
+
+    file.write_all(&[0x3B])?;
+    file.flush()
+}
+
+// Variable-length codes, written least significant bit first.
+struct Bits {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32
+}
+
+impl Bits {
+    fn put(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// LZW compression of an indexed image. The code size grows one code after the
+// table reaches the power of two, as the decoder expects; a full table
+// is emptied by a CLEAR code.
+fn compress(indices: &[u8]) -> Vec<u8> {
+    let mut bits = Bits { bytes: Vec::new(), buffer: 0, count: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size: u32 = 9;
+    let mut next: u16 = END_CODE + 1;
+
+    bits.put(CLEAR_CODE, size);
+    let mut prefix = match indices.first() {
+        Some(index) => *index as u16,
+        None => {
+            bits.put(END_CODE, size);
+            return bits.finish();
+        }
+    };
+
+    for &index in &indices[1..] {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        bits.put(prefix, size);
+        if next as u32 >= 1 << size && size < MAX_CODE_SIZE {
+            size += 1;
+        }
+        if next < 1 << MAX_CODE_SIZE {
+            table.insert((prefix, index), next);
+            next += 1;
+        }
+        else {
+            bits.put(CLEAR_CODE, size);
+            table.clear();
+            next = END_CODE + 1;
+            size = 9;
+        }
+        prefix = index as u16;
+    }
+
+    bits.put(prefix, size);
+    if next as u32 >= 1 << size && size < MAX_CODE_SIZE {
+        size += 1;
+    }
+    bits.put(END_CODE, size);
+    bits.finish()
+}
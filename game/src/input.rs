@@ -0,0 +1,256 @@
+use sdl2::keyboard::Keycode;
+
+use crate::utils::{Angle, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const DRAG_THRESHOLD: f64 = 20.0;
+
+// Aiming with the mouse or a finger: the cursor orients the shot, and a slingshot-style
+// drag-and-release fires in the direction opposite to the drag.
+pub struct Pointer {
+    drag_start: Option<(i32, i32)>,
+    pulled: bool
+}
+
+impl Pointer {
+    pub fn new() -> Self {
+        Pointer {
+            drag_start: None,
+            pulled: false
+        }
+    }
+
+    pub fn in_play_field(x: i32, y: i32) -> bool {
+        x >= 105 && x <= WINDOW_WIDTH as i32 - 105 && y >= 80 && y <= WINDOW_HEIGHT as i32
+    }
+
+    pub fn from_finger(x: f32, y: f32) -> (i32, i32) {
+        ((x * WINDOW_WIDTH as f32) as i32, (y * WINDOW_HEIGHT as f32) as i32)
+    }
+
+    pub fn press(&mut self, x: i32, y: i32) {
+        if Pointer::in_play_field(x, y) {
+            self.drag_start = Some((x, y));
+            self.pulled = false;
+        }
+    }
+
+    pub fn motion(&mut self, x: i32, y: i32, angle: &mut Angle) {
+        match self.drag_start {
+            Some((start_x, start_y)) => {
+                let dx = (start_x - x) as f64;
+                let dy = (start_y - y) as f64;
+                if dx.hypot(dy) >= DRAG_THRESHOLD {
+                    self.pulled = true;
+                    angle.toward(dx, dy);
+                }
+            },
+            None => {
+                angle.toward(
+                    (x - WINDOW_WIDTH as i32 / 2) as f64,
+                    (y - WINDOW_HEIGHT as i32) as f64
+                );
+            }
+        }
+    }
+
+    // Returns true if the release should launch the round.
+    pub fn release(&mut self) -> bool {
+        let launch = self.drag_start.is_some() && self.pulled;
+        self.drag_start = None;
+        self.pulled = false;
+        launch
+    }
+
+    pub fn cancel(&mut self) {
+        self.drag_start = None;
+        self.pulled = false;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    AimLeft,
+    AimRight,
+    FineAim,
+    Launch,
+    Pause,
+    Recall,
+    FastForward,
+    Mute,
+    Record,
+    Screenshot,
+    SaveClip,
+    Fullscreen,
+    Back
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::AimLeft, Action::AimRight, Action::FineAim, Action::Launch, Action::Pause, Action::Recall,
+        Action::FastForward, Action::Mute, Action::Record, Action::Screenshot, Action::SaveClip, Action::Fullscreen,
+        Action::Back
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::AimLeft => "aim_left",
+            Action::AimRight => "aim_right",
+            Action::FineAim => "fine_aim",
+            Action::Launch => "launch",
+            Action::Pause => "pause",
+            Action::Recall => "recall",
+            Action::FastForward => "fast_forward",
+            Action::Mute => "mute",
+            Action::Record => "record",
+            Action::Screenshot => "screenshot",
+            Action::SaveClip => "save_clip",
+            Action::Fullscreen => "fullscreen",
+            Action::Back => "back"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.iter().find(|action| action.name() == name).copied()
+    }
+}
+
+// Key -> action mapping. An action can have several keys
+// (Return and Space to launch), but a key serves only one action.
+#[derive(Clone)]
+pub struct Bindings {
+    keys: Vec<(Action, Keycode)>
+}
+
+impl Bindings {
+    pub fn defaults() -> Self {
+        Bindings {
+            keys: vec![
+                (Action::AimLeft, Keycode::Left),
+                (Action::AimRight, Keycode::Right),
+                (Action::FineAim, Keycode::LShift),
+                (Action::Launch, Keycode::Return),
+                (Action::Launch, Keycode::Space),
+                (Action::Pause, Keycode::P),
+                (Action::Recall, Keycode::R),
+                (Action::FastForward, Keycode::F),
+                (Action::Mute, Keycode::M),
+                (Action::Record, Keycode::F9),
+                (Action::SaveClip, Keycode::F10),
+                (Action::Fullscreen, Keycode::F11),
+                (Action::Screenshot, Keycode::F12),
+                (Action::Back, Keycode::Escape)
+            ]
+        }
+    }
+
+    // The keys given for an action replace all of its default ones.
+    pub fn with_overrides(overrides: &[(Action, Keycode)]) -> Self {
+        let mut bindings = Bindings::defaults();
+        for (action, _) in overrides {
+            bindings.clear(*action);
+        }
+        for (action, key) in overrides {
+            bindings.add(*action, *key);
+        }
+        bindings
+    }
+
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        self.keys.iter().find(|(_, bound)| *bound == key).map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<Keycode> {
+        self.keys.iter().filter(|(bound, _)| *bound == action).map(|(_, key)| *key).collect()
+    }
+
+    pub fn add(&mut self, action: Action, key: Keycode) {
+        self.keys.push((action, key));
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.keys.retain(|(bound, _)| *bound != action);
+    }
+
+    // Replaces the action's keys; refuses a key already taken by another action.
+    pub fn bind(&mut self, action: Action, key: Keycode) -> Result<(), Action> {
+        match self.action(key) {
+            Some(other) if other != action => Err(other),
+            _ => {
+                self.clear(action);
+                self.keys.push((action, key));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn conflicts(&self) -> Vec<(Keycode, Action, Action)> {
+        let mut conflicts = Vec::new();
+        for (i, (action, key)) in self.keys.iter().enumerate() {
+            for (other, other_key) in self.keys.iter().skip(i + 1) {
+                if key == other_key && action != other {
+                    conflicts.push((*key, *action, *other));
+                }
+            }
+        }
+        conflicts
+    }
+
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.keys(action).iter().map(|key| key.name()).collect();
+        if names.is_empty() {
+            String::from("-")
+        }
+        else {
+            names.join(" / ")
+        }
+    }
+
+    pub fn entries(&self) -> &[(Action, Keycode)] {
+        &self.keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_have_no_conflict() {
+        assert!(Bindings::defaults().conflicts().is_empty());
+    }
+
+    #[test]
+    fn conflicts_list_keys_bound_twice() {
+        let mut bindings = Bindings::defaults();
+        bindings.add(Action::Mute, Keycode::P);
+        assert_eq!(bindings.conflicts(), vec![(Keycode::P, Action::Pause, Action::Mute)]);
+    }
+
+    #[test]
+    fn bind_refuses_a_key_of_another_action() {
+        let mut bindings = Bindings::defaults();
+        assert_eq!(bindings.bind(Action::Mute, Keycode::P), Err(Action::Pause));
+        assert_eq!(bindings.keys(Action::Mute), vec![Keycode::M]);
+        assert_eq!(bindings.action(Keycode::P), Some(Action::Pause));
+    }
+
+    #[test]
+    fn bind_replaces_the_keys_of_the_action() {
+        let mut bindings = Bindings::defaults();
+        assert!(bindings.bind(Action::Launch, Keycode::Space).is_ok());
+        assert_eq!(bindings.keys(Action::Launch), vec![Keycode::Space]);
+        assert!(bindings.action(Keycode::Return).is_none());
+
+        assert!(bindings.bind(Action::Back, Keycode::Backspace).is_ok());
+        assert!(bindings.bind(Action::Launch, Keycode::Escape).is_ok());
+        assert_eq!(bindings.action(Keycode::Escape), Some(Action::Launch));
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults_of_their_action_only() {
+        let bindings = Bindings::with_overrides(&[(Action::Launch, Keycode::L), (Action::Launch, Keycode::K)]);
+        assert_eq!(bindings.keys(Action::Launch), vec![Keycode::L, Keycode::K]);
+        assert_eq!(bindings.keys(Action::Pause), vec![Keycode::P]);
+        assert!(bindings.conflicts().is_empty());
+    }
+}
@@ -0,0 +1,445 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{self, GameError};
+
+pub const LOCALES_DIR: &str = "locales";
+pub const LOCALE_FILE: &str = "main.ftl";
+pub const DEFAULT_LANGUAGE: &str = "en";
+const MAX_DEPTH: u32 = 8;
+
+// Line number and message of a syntax error.
+type ParseResult<T> = Result<T, (usize, String)>;
+
+// The subset of Fluent (https://projectfluent.org) the game needs: messages and terms,
+// `{ $variable }`, `{ message }` / `{ -term }` references and selectors, where a number
+// picks its plural category (`[one]`, `[other]`...) unless a variant matches it exactly.
+#[derive(Clone)]
+enum Element {
+    Text(String),
+    Variable(String),
+    Reference(String),
+    Select {
+        variable: String,
+        variants: Vec<(String, Vec<Element>)>,
+        default: usize
+    }
+}
+
+pub struct Locale {
+    pub language: String,
+    messages: HashMap<String, Vec<Element>>,
+    // Ids already reported as missing, so that the HUD does not fill the log.
+    missing: RefCell<HashSet<String>>
+}
+
+impl Locale {
+    // Missing messages fall back to English, then to their id.
+    pub fn load(root: &Path, language: &str) -> Result<Self, GameError> {
+        let mut messages = HashMap::new();
+        for name in [DEFAULT_LANGUAGE, language] {
+            let path = root.join(name).join(LOCALE_FILE);
+            let source = fs::read_to_string(&path)
+                .map_err(|e| GameError::Assets(format!("cannot read {}: {}", path.display(), e)))?;
+            let entries = Parser::new(&source).entries()
+                .map_err(|(line, message)| GameError::Assets(format!("{}:{}: {}", path.display(), line, message)))?;
+            messages.extend(entries);
+        }
+
+        Ok(Locale {
+            language: String::from(language),
+            messages,
+            missing: RefCell::new(HashSet::new())
+        })
+    }
+
+    // An empty catalog that shows message ids, when no locale could be read.
+    pub fn empty() -> Self {
+        Locale {
+            language: String::from(DEFAULT_LANGUAGE),
+            messages: HashMap::new(),
+            missing: RefCell::new(HashSet::new())
+        }
+    }
+
+    // Folders of `root` that contain a main.ftl, sorted, English first.
+    pub fn discover(root: &Path) -> Vec<String> {
+        let mut names: Vec<String> = match fs::read_dir(root) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().join(LOCALE_FILE).is_file())
+                .filter_map(|entry| entry.file_name().to_str().map(String::from))
+                .filter(|name| name != DEFAULT_LANGUAGE)
+                .collect(),
+            Err(_) => Vec::new()
+        };
+        names.sort();
+        names.insert(0, String::from(DEFAULT_LANGUAGE));
+        names
+    }
+
+    // `fr_FR.UTF-8` -> `fr`, from the usual environment variables.
+    pub fn system_language() -> String {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+            .and_then(|value| value.split(['_', '.', '-']).next().map(|language| language.to_lowercase()))
+            .unwrap_or_else(|| String::from(DEFAULT_LANGUAGE))
+    }
+
+    pub fn get(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    pub fn format(&self, id: &str, args: &[(&str, &str)]) -> String {
+        let mut out = String::new();
+        match self.messages.get(id) {
+            Some(pattern) => self.resolve(pattern, args, &mut out, 0),
+            None => {
+                if self.missing.borrow_mut().insert(String::from(id)) {
+                    error::log(&format!("missing translation `{}` ({})", id, self.language));
+                }
+                out.push_str(id);
+            }
+        }
+        out
+    }
+
+    fn resolve(&self, pattern: &[Element], args: &[(&str, &str)], out: &mut String, depth: u32) {
+        for element in pattern {
+            match element {
+                Element::Text(text) => out.push_str(text),
+                Element::Variable(name) => match args.iter().find(|(arg, _)| arg == name) {
+                    Some((_, value)) => out.push_str(value),
+                    None => out.push_str(&format!("{{${}}}", name))
+                },
+                Element::Reference(id) => match self.messages.get(id) {
+                    Some(referenced) if depth < MAX_DEPTH => self.resolve(referenced, args, out, depth + 1),
+                    _ => out.push_str(&format!("{{{}}}", id))
+                },
+                Element::Select { variable, variants, default } => {
+                    let value = args.iter().find(|(arg, _)| arg == variable).map(|(_, value)| *value).unwrap_or("");
+                    let category = value.parse::<f64>().ok().map(|number| self.plural(number));
+                    let chosen = variants.iter().position(|(key, _)| key == value)
+                        .or_else(|| variants.iter().position(|(key, _)| Some(key.as_str()) == category))
+                        .unwrap_or(*default);
+                    self.resolve(&variants[chosen].1, args, out, depth + 1);
+                }
+            }
+        }
+    }
+
+    // CLDR cardinal rules, reduced to the categories our languages use.
+    fn plural(&self, number: f64) -> &'static str {
+        let integer = number.fract() == 0.0;
+        match self.language.as_str() {
+            "fr" if (0.0..2.0).contains(&number) => "one",
+            "fr" => "other",
+            _ if integer && number == 1.0 => "one",
+            _ => "other"
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Parser {
+            chars: source.chars().filter(|c| *c != '\r').collect(),
+            pos: 0,
+            line: 1
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t') | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        }
+        else {
+            Err((self.line, format!("expected `{}`", expected)))
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut id = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_') {
+            id.push(c);
+            self.bump();
+        }
+        id
+    }
+
+    fn entries(mut self) -> ParseResult<Vec<(String, Vec<Element>)>> {
+        let mut entries = Vec::new();
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(entries),
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                },
+                Some(_) => {
+                    let id = self.identifier();
+                    if id.is_empty() {
+                        return Err((self.line, String::from("expected a message id")));
+                    }
+                    self.skip_spaces();
+                    self.expect('=')?;
+                    let pattern = self.pattern(false)?;
+                    entries.push((id, pattern));
+                }
+            }
+        }
+    }
+
+    // A message value continues on the next line only if that line is indented; a variant
+    // ends before the next `[key]`, `*[key]` or the closing `}` of its selector.
+    fn ends_at_line(&self, in_variant: bool) -> bool {
+        let next = self.chars[self.pos + 1..].iter().find(|c| **c != ' ' && **c != '\t');
+        let indented = matches!(self.chars.get(self.pos + 1), Some(' ') | Some('\t'));
+        !indented || (in_variant && matches!(next, Some('[') | Some('*') | Some('}')))
+    }
+
+    fn pattern(&mut self, in_variant: bool) -> ParseResult<Vec<Element>> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    self.bump();
+                    if !text.is_empty() {
+                        elements.push(Element::Text(std::mem::take(&mut text)));
+                    }
+                    elements.push(self.placeable()?);
+                },
+                '}' if in_variant => break,
+                '\n' if self.ends_at_line(in_variant) => break,
+                '\n' => {
+                    text.push('\n');
+                    self.bump();
+                    self.skip_spaces();
+                },
+                _ => {
+                    text.push(c);
+                    self.bump();
+                }
+            }
+        }
+        if !text.is_empty() {
+            elements.push(Element::Text(text));
+        }
+
+        if let Some(Element::Text(first)) = elements.first_mut() {
+            *first = String::from(first.trim_start());
+        }
+        if let Some(Element::Text(last)) = elements.last_mut() {
+            *last = String::from(last.trim_end());
+        }
+        Ok(elements)
+    }
+
+    fn placeable(&mut self) -> ParseResult<Element> {
+        self.skip_blank();
+        let element = match self.peek() {
+            Some('$') => {
+                self.bump();
+                let variable = self.identifier();
+                self.skip_blank();
+                if self.peek() == Some('-') && self.chars.get(self.pos + 1) == Some(&'>') {
+                    self.bump();
+                    self.bump();
+                    self.select(variable)?
+                }
+                else {
+                    Element::Variable(variable)
+                }
+            },
+            Some('"') => {
+                self.bump();
+                let mut literal = String::new();
+                while let Some(c) = self.peek().filter(|c| *c != '"' && *c != '\n') {
+                    literal.push(c);
+                    self.bump();
+                }
+                self.expect('"')?;
+                Element::Text(literal)
+            },
+            Some(_) => {
+                let id = self.identifier();
+                if id.is_empty() {
+                    return Err((self.line, String::from("expected a variable, a string or a message reference")));
+                }
+                Element::Reference(id)
+            },
+            None => return Err((self.line, String::from("unclosed `{`")))
+        };
+        self.skip_blank();
+        self.expect('}')?;
+        Ok(element)
+    }
+
+    fn select(&mut self, variable: String) -> ParseResult<Element> {
+        let mut variants = Vec::new();
+        let mut default = None;
+
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some('*') => {
+                    self.bump();
+                    default = Some(variants.len());
+                },
+                Some('[') => {},
+                _ => break
+            }
+            self.expect('[')?;
+            self.skip_spaces();
+            let key = self.identifier();
+            self.skip_spaces();
+            self.expect(']')?;
+            let pattern = self.pattern(true)?;
+            variants.push((key, pattern));
+        }
+
+        match default {
+            Some(default) => Ok(Element::Select { variable, variants, default }),
+            None => Err((self.line, String::from("a selector needs a default `*[...]` variant")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "\
+# Comment
+balls = { $count ->
+    [one] { $count } ball
+   *[other] { $count } balls
+}
+rows = { $count ->
+    [0] No rows
+   *[other] { $count } rows
+}
+-brand = Concrete
+welcome = Welcome to { -brand }, { $name }!
+";
+
+    const FR: &str = "\
+balls = { $count ->
+    [one] { $count } balle
+   *[other] { $count } balles
+}
+";
+
+    fn catalog(language: &str, sources: &[&str]) -> Locale {
+        let mut messages = HashMap::new();
+        for source in sources {
+            messages.extend(Parser::new(source).entries().unwrap());
+        }
+        Locale {
+            language: String::from(language),
+            messages,
+            missing: RefCell::new(HashSet::new())
+        }
+    }
+
+    #[test]
+    fn english_plurals() {
+        let en = catalog("en", &[EN]);
+        assert_eq!(en.format("balls", &[("count", "1")]), "1 ball");
+        assert_eq!(en.format("balls", &[("count", "3")]), "3 balls");
+        assert_eq!(en.format("balls", &[("count", "0")]), "0 balls");
+        assert_eq!(en.format("balls", &[("count", "1.5")]), "1.5 balls");
+    }
+
+    #[test]
+    fn french_plurals_count_zero_and_one_as_one() {
+        let fr = catalog("fr", &[EN, FR]);
+        assert_eq!(fr.format("balls", &[("count", "0")]), "0 balle");
+        assert_eq!(fr.format("balls", &[("count", "1")]), "1 balle");
+        assert_eq!(fr.format("balls", &[("count", "1.5")]), "1.5 balle");
+        assert_eq!(fr.format("balls", &[("count", "3")]), "3 balles");
+    }
+
+    #[test]
+    fn exact_variants_win_over_plural_categories() {
+        let en = catalog("en", &[EN]);
+        assert_eq!(en.format("rows", &[("count", "0")]), "No rows");
+        assert_eq!(en.format("rows", &[("count", "2")]), "2 rows");
+        assert_eq!(en.format("rows", &[]), "{$count} rows");
+    }
+
+    #[test]
+    fn variables_and_terms_are_substituted() {
+        let en = catalog("en", &[EN]);
+        assert_eq!(en.format("welcome", &[("name", "Ada")]), "Welcome to Concrete, Ada!");
+        assert_eq!(en.format("welcome", &[]), "Welcome to Concrete, {$name}!");
+    }
+
+    #[test]
+    fn missing_ids_fall_back_and_are_reported_once() {
+        let fr = catalog("fr", &[FR]);
+        assert_eq!(fr.get("welcome"), "welcome");
+        assert_eq!(fr.get("welcome"), "welcome");
+        assert_eq!(fr.missing.borrow().len(), 1);
+        assert!(fr.missing.borrow().contains("welcome"));
+
+        // Messages only in the English catalog are used by other languages.
+        let fr = catalog("fr", &[EN, FR]);
+        assert_eq!(fr.format("welcome", &[("name", "Ada")]), "Welcome to Concrete, Ada!");
+        assert!(fr.missing.borrow().is_empty());
+    }
+
+    #[test]
+    fn malformed_entries_are_rejected() {
+        for source in [
+            "= no id\n",
+            "title Concrete\n",
+            "title = { $name\n",
+            "title = {\n",
+            "title = { \"unclosed }\n",
+            "balls = { $count ->\n    [one] ball\n    [other] balls\n}\n",
+            "balls = { $count ->\n   *[other balls\n}\n"
+        ] {
+            assert!(Parser::new(source).entries().is_err(), "accepted {:?}", source);
+        }
+        assert_eq!(Parser::new("ok = fine\n\n\ntitle Concrete\n").entries().err().map(|(line, _)| line), Some(4));
+    }
+}
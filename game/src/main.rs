@@ -1,120 +1,420 @@
+mod assets;
+mod audio;
+mod camera;
+mod capture;
+mod error;
 mod game;
+mod gamepad;
+mod gif;
+mod input;
+mod locale;
+mod particles;
+mod record;
+mod replay;
+mod score;
+mod screen;
+mod settings;
+mod text;
+mod theme;
+mod tween;
+mod ui;
 mod utils;
+mod view;
 
-use sdl2::mixer::{InitFlag, AUDIO_S16LSB, DEFAULT_CHANNELS};
-use sdl2::image::{self, LoadTexture};
+use sdl2::image;
 use sdl2::video::Window;
+use sdl2::controller::Button;
 use sdl2::keyboard::Keycode;
-use std::path::Path;
 use crate::utils::{WINDOW_WIDTH, WINDOW_HEIGHT};
+use crate::assets::Assets;
+use crate::audio::Audio;
+use crate::capture::{Clip, CLIP_FRAME_STEP};
+use crate::error::GameError;
 use crate::game::Game;
+use crate::screen::Screen;
+use crate::gamepad::Gamepads;
+use crate::input::{Action, Pointer};
+use crate::record::{Recorder, SAMPLES_PER_FRAME};
+use crate::replay::Replay;
+use crate::view::View;
 use sdl2::event::Event;
 use sdl2::Sdl;
-use std::time::Duration;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Mouse events synthesized by SDL from touch carry this id;
+// touch is already handled by the Finger* events.
+const TOUCH_MOUSE_ID: u32 = 0xFFFFFFFF;
 
 fn main() {
-    let sdl_context: Sdl = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
+    if let Err(e) = run() {
+        error::log(&format!("fatal: {}", e));
+        std::process::exit(1);
+    }
+}
+
+// Value of a `--name value` or `--name=value` option.
+pub(crate) fn arg_value(args: &[String], name: &str) -> Option<String> {
+    for (i, arg) in args.iter().enumerate() {
+        if let Some(value) = arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(String::from(value));
+        }
+        if arg == name {
+            return args.get(i + 1).cloned();
+        }
+    }
+    None
+}
+
+fn toggle_recording(recorder: &mut Option<Recorder>, audio: &mut Audio, game: &mut Game) {
+    if let Some(recording) = recorder.take() {
+        audio.capture(false);
+        if let Some(path) = finish_recording(recording) {
+            game.notify("notice-recording-saved", &path);
+        }
+        return;
+    }
+
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    let path = format!("recording-{}.mp4", seconds);
+    match Recorder::start(Path::new(&path), WINDOW_WIDTH, WINDOW_HEIGHT) {
+        Ok(recording) => {
+            game.notify("notice-recording", Path::new(&path));
+            audio.capture(true);
+            *recorder = Some(recording);
+        },
+        Err(e) => error::log(&format!("cannot record {}: {}", path, e))
+    }
+}
+
+fn finish_recording(recording: Recorder) -> Option<PathBuf> {
+    recording.finish().map_err(|e| error::log(&format!("{}", e))).ok()
+}
+
+fn run() -> Result<(), GameError> {
+    let args: Vec<String> = std::env::args().collect();
+    let assets = Assets::load(Assets::find_root(&args))?;
+
+    // Without a window or sound card (CI), a replay runs as fast as possible,
+    // for example to turn it into a video with --record.
+    let headless = args.iter().any(|arg| arg == "--headless");
+    let playback = match arg_value(&args, "--replay") {
+        Some(path) => Some(Replay::load(Path::new(&path))?),
+        None => None
+    };
+    if headless {
+        if playback.is_none() {
+            return Err(GameError::Record(String::from("--headless needs --replay <file>")));
+        }
+        std::env::set_var("SDL_VIDEODRIVER", "dummy");
+        std::env::set_var("SDL_AUDIODRIVER", "dummy");
+    }
+
+    let sdl_context: Sdl = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+    let controllers = sdl_context.game_controller().map_err(|e| error::log(&format!("gamepads disabled: {}", e))).ok();
+    let mut gamepads = Gamepads::new(controllers);
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
     let _image_context = image::init(image::InitFlag::PNG);
 
-    let window: Window = video_subsystem
+    // The window resizes freely; the game keeps its logical resolution (see view.rs).
+    let mut window: Window = video_subsystem
         .window("Brick Breaker", WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32)
         .position_centered()
+        .resizable()
+        .allow_highdpi()
         .build()
-        .unwrap();
+        .map_err(|e| e.to_string())?;
+    let _ = window.set_minimum_size(WINDOW_WIDTH / 2, WINDOW_HEIGHT / 2);
 
-    let mut canvas = window.into_canvas().build().unwrap();
+    sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "1");
+    let canvas_builder = if headless { window.into_canvas().software() } else { window.into_canvas() };
+    let mut canvas = canvas_builder.build().map_err(|e| e.to_string())?;
     let texture_creator = canvas.texture_creator();
+    let mut view = View::new(&mut canvas, &texture_creator)?;
+
+    let mut audio = Audio::open(&assets);
+
+    let mut game = Game::new(&assets, &ttf_context, &texture_creator)?;
+    game.load_content();
+    if let Some(mode) = arg_value(&args, "--mode") {
+        game.set_mode(&mode);
+    }
+    match &playback {
+        Some(replay) => {
+            game.set_mode(&replay.mode);
+            game.next_seed = Some(replay.seed);
+            game.record_scores = false;
+            game.set_screen(Screen::Playing);
+        },
+        None => game.set_screen(Screen::Menu)
+    }
 
-    let mut game = Game::new(&ttf_context, &texture_creator);
-
-    let frequency = 44_100;
-    let format = AUDIO_S16LSB;
-    let channels = DEFAULT_CHANNELS;
-    let chunk_size = 1_024;
-    sdl2::mixer::open_audio(frequency, format, channels, chunk_size).unwrap();
-    let _mixer_context = sdl2::mixer::init(InitFlag::MP3 | InitFlag::FLAC | InitFlag::MOD | InitFlag::OGG).unwrap();
-    sdl2::mixer::allocate_channels(10);
-
-    let home_music_chunk = sdl2::mixer::Chunk::from_file(Path::new("retro-game-arcade-236133.mp3")).unwrap();
-    let background_ig_music_chunk = sdl2::mixer::Chunk::from_file(Path::new("background-music.mp3")).unwrap();
-    let broken_brick_chunk = sdl2::mixer::Chunk::from_file(Path::new("broken_brick.mp3")).unwrap();
-    let new_ball_chunk = sdl2::mixer::Chunk::from_file(Path::new("new_ball.mp3")).unwrap();
-    let bricks_down_chunk = sdl2::mixer::Chunk::from_file(Path::new("bricks_down.mp3")).unwrap();
-    let new_wave_chunk = sdl2::mixer::Chunk::from_file(Path::new("new_wave.mp3")).unwrap();
-    let brick_exploding_chunk = sdl2::mixer::Chunk::from_file(Path::new("brick_exploding.mp3")).unwrap();
-    let more_damage_chunk = sdl2::mixer::Chunk::from_file(Path::new("more_damage.mp3")).unwrap();
-    let more_bullets_chunk = sdl2::mixer::Chunk::from_file(Path::new("more_bullets.mp3")).unwrap();
-    let bigger_balls_chunk = sdl2::mixer::Chunk::from_file(Path::new("bigger_balls.mp3")).unwrap();
-    sdl2::mixer::Channel(0).set_volume(60);
-    sdl2::mixer::Channel(1).set_volume(30);
-    sdl2::mixer::Channel(2).set_volume(80);
-    sdl2::mixer::Channel(3).set_volume(60);
-    sdl2::mixer::Channel(4).set_volume(50);
-    sdl2::mixer::Channel(5).set_volume(90);
-    sdl2::mixer::Channel(6).set_volume(60);
-    sdl2::mixer::Channel(7).set_volume(60);
-    sdl2::mixer::Channel(8).set_volume(60);
-    sdl2::mixer::Channel(9).set_volume(60);
-    sdl2::mixer::Channel(0).play(&home_music_chunk, 2).unwrap();
-
-    game.load_content(&ttf_context, &texture_creator);
-
-    let ball_texture = texture_creator
-        .load_texture(Path::new("white-circle.png"))
-        .unwrap();
-
-
-    let mut event_pump = sdl_context.event_pump().unwrap();
+    let mut recorder: Option<Recorder> = None;
+    if let Some(path) = arg_value(&args, "--record") {
+        recorder = Some(Recorder::start(Path::new(&path), WINDOW_WIDTH, WINDOW_HEIGHT)?);
+        audio.capture(true);
+    }
+
+
+    let mut event_pump = sdl_context.event_pump()?;
     let mut frame: i32 = 0;
+    let mut pointer = Pointer::new();
+    let mut fine_aim = false;
+    let mut next_launch: usize = 0;
+    let mut next_recall: usize = 0;
+    let mut clip = Clip::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+    let mut in_round = false;
+    let mut screenshot = false;
+    // Last mouse position, in logical coordinates, for the wheel.
+    let mut mouse = (0, 0);
 
     'running: loop {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } => break 'running,
-                Event::MouseButtonDown { x, y, .. } => {
-                    game.act_drawn(x, y, &home_music_chunk, &background_ig_music_chunk);
+                Event::MouseButtonDown { x, y, which, .. } => {
+                    if !game.pointer_pressed(x, y) && game.is_aiming() && which != TOUCH_MOUSE_ID {
+                        pointer.press(x, y);
+                    }
                 },
-                Event::KeyDown { keycode: Some(key), .. } => {
+                Event::MouseMotion { x, y, which, .. } => {
+                    mouse = (x, y);
+                    game.pointer_moved(x, y);
+                    if game.is_aiming() && which != TOUCH_MOUSE_ID && Pointer::in_play_field(x, y) {
+                        pointer.motion(x, y, &mut game.angle);
+                    }
+                },
+                Event::MouseButtonUp { x, y, which, .. } => {
+                    game.pointer_released(x, y);
+                    if which != TOUCH_MOUSE_ID && pointer.release() {
+                        game.launch();
+                    }
+                },
+                Event::MouseWheel { y, .. } => game.scroll(mouse.0, mouse.1, y),
+                Event::FingerDown { x, y, .. } if game.is_aiming() => {
+                    let (x, y) = Pointer::from_finger(x, y);
+                    pointer.press(x, y);
+                },
+                Event::FingerMotion { x, y, .. } if game.is_aiming() => {
+                    let (x, y) = Pointer::from_finger(x, y);
+                    pointer.motion(x, y, &mut game.angle);
+                },
+                Event::FingerUp { .. } => {
+                    if pointer.release() {
+                        game.launch();
+                    }
+                },
+                Event::ControllerDeviceAdded { which, .. } => gamepads.add(which),
+                Event::ControllerDeviceRemoved { which, .. } => gamepads.remove(which),
+                Event::ControllerAxisMotion { which, axis, value, .. } => gamepads.axis(which, axis, value),
+                Event::ControllerButtonDown { button, .. } => {
+                    match button {
+                        Button::A if game.is_aiming() => game.launch(),
+                        Button::A => game.act_focused(),
+                        Button::X if !game.is_in_menu() => game.recall(),
+                        Button::Y if !game.is_in_menu() => game.toggle_fast_forward(),
+                        Button::B => if !game.back() {break 'running},
+                        Button::Start => {
+                            let name = if game.screen() == Screen::Paused { "pause_resume" } else { "pause_button" };
+                            game.act_button(name);
+                        },
+                        Button::DPadUp if game.is_in_menu() => game.move_focus(-1),
+                        Button::DPadDown if game.is_in_menu() => game.move_focus(1),
+                        Button::DPadLeft if game.is_in_menu() => game.adjust(-1),
+                        Button::DPadRight if game.is_in_menu() => game.adjust(1),
+                        Button::DPadLeft => game.angle.incr(),
+                        Button::DPadRight => game.angle.decr(),
+                        _ => {}
+                    }
+                },
+                Event::KeyDown { keycode: Some(key), .. } if game.rebinding.is_some() => {
+                    game.rebind(key);
+                },
+                // Menus are always driven by the arrows, Enter and Escape, whatever the bindings.
+                Event::KeyDown { keycode: Some(key @ (Keycode::Up | Keycode::Down | Keycode::Left | Keycode::Right | Keycode::Return | Keycode::KpEnter | Keycode::Escape)), .. } if game.is_in_menu() => {
                     match key {
-                        Keycode::Escape => break 'running,
-                        Keycode::Left => game.angle.incr(),
-                        Keycode::Right => game.angle.decr(),
-                        Keycode::Return | Keycode::Space => if !game.round {game.round = true},
+                        Keycode::Up => game.move_focus(-1),
+                        Keycode::Down => game.move_focus(1),
+                        Keycode::Left => game.adjust(-1),
+                        Keycode::Right => game.adjust(1),
+                        Keycode::Escape => if !game.back() {break 'running},
+                        _ => game.act_focused()
+                    }
+                },
+                Event::KeyDown { keycode: Some(key), .. } if game.settings.bindings.action(key) == Some(Action::Record) => {
+                    toggle_recording(&mut recorder, &mut audio, &mut game);
+                },
+                // The capture is taken on the next frame, before it is presented.
+                Event::KeyDown { keycode: Some(key), .. } if game.settings.bindings.action(key) == Some(Action::Screenshot) => {
+                    screenshot = true;
+                },
+                Event::KeyDown { keycode: Some(key), .. } if game.settings.bindings.action(key) == Some(Action::SaveClip) => {
+                    if clip.is_empty() {
+                        error::log("no round to save yet");
+                    }
+                    else {
+                        let path = capture::file_name("round", game.wave.wave_number, game.replay.seed, "gif");
+                        match clip.save_gif(&path) {
+                            Ok(()) => game.notify("notice-clip-saved", &path),
+                            Err(e) => error::log(&format!("cannot save {}: {}", path.display(), e))
+                        }
+                    }
+                },
+                Event::KeyDown { keycode: Some(key), .. } if game.settings.bindings.action(key) == Some(Action::Fullscreen) => {
+                    game.toggle_fullscreen();
+                },
+                Event::KeyDown { keycode: Some(key), .. } if game.is_in_menu() => {
+                    match game.settings.bindings.action(key) {
+                        Some(Action::Back) => if !game.back() {break 'running},
+                        Some(Action::Mute) => game.toggle_mute(),
+                        Some(Action::Pause) if game.screen() == Screen::Paused => {
+                            game.act_button("pause_resume");
+                        },
                         _ => {}
                     }
-                }
+                },
+                Event::KeyDown { keycode: Some(key), .. } => {
+                    match game.settings.bindings.action(key) {
+                        Some(Action::Back) => {
+                            game.back();
+                        },
+                        Some(Action::AimLeft) if fine_aim => game.angle.rotate(PI / 800.0),
+                        Some(Action::AimRight) if fine_aim => game.angle.rotate(-PI / 800.0),
+                        Some(Action::AimLeft) => game.angle.incr(),
+                        Some(Action::AimRight) => game.angle.decr(),
+                        Some(Action::FineAim) => fine_aim = true,
+                        Some(Action::Mute) => game.toggle_mute(),
+                        Some(Action::Launch) => game.launch(),
+                        Some(Action::Recall) => game.recall(),
+                        Some(Action::FastForward) => game.toggle_fast_forward(),
+                        Some(Action::Pause) => {
+                            game.act_button("pause_button");
+                        },
+                        _ => {}
+                    }
+                },
+                Event::KeyUp { keycode: Some(key), .. } if game.settings.bindings.action(key) == Some(Action::FineAim) => {
+                    fine_aim = false;
+                },
                 _ => {}
             }
         }
 
+        if game.is_aiming() {
+            gamepads.aim(&mut game.angle);
+        }
+        else {
+            pointer.cancel();
+        }
+
         if !game.game_is_loaded {
-            game.load_bricks(&ttf_context, &texture_creator);
+            game.load_bricks();
         }
 
-        if !game.paused {
-            game.update_balls_state(frame, &ttf_context, &texture_creator, &new_ball_chunk, &bricks_down_chunk, &new_wave_chunk);
+        if let Some(launch) = playback.as_ref().and_then(|replay| replay.launches.get(next_launch)) {
+            if game.is_aiming() && game.tick >= launch.tick {
+                game.angle.set(launch.angle);
+                game.launch();
+                next_launch += 1;
+            }
         }
 
-        match (game.started, game.paused, game.game_is_lost) {
-            (_, _, true) => {
-                canvas = game.display_loss(canvas);
+        if let Some(tick) = playback.as_ref().and_then(|replay| replay.recalls.get(next_recall)) {
+            if game.round && game.tick >= *tick {
+                game.recall();
+                next_recall += 1;
             }
-            (true, false, _) => {
-                canvas = game.display_balls_and_bricks(canvas, &ball_texture, frame,  &ttf_context, &texture_creator, &broken_brick_chunk, &more_bullets_chunk, &more_damage_chunk, &bigger_balls_chunk, &brick_exploding_chunk);
-            },
-            (true, true, _) => {
-                canvas = game.display_pause(canvas);
+        }
+
+        // A replay keeps one step per frame so that its recalls land on their exact tick;
+        // a batch stops with the round, so that the next launch is never late.
+        let steps = if playback.is_some() { 1 } else { game.steps_per_frame() };
+        for _ in 0..steps {
+            if game.screen() != Screen::Playing {
+                break;
+            }
+            game.update_balls_state();
+            if !game.round {
+                break;
+            }
+        }
+
+        game.update_rebinding();
+        game.refresh_ui();
+
+        audio.set_volumes(&game.settings);
+        for event in game.sounds.drain(..) {
+            audio.handle(event);
+        }
+        audio.update();
+
+        if !headless {
+            view.set_fullscreen(&mut canvas, game.settings.fullscreen);
+        }
+
+        canvas = view.begin(canvas);
+        match game.screen() {
+            Screen::Playing => {
+                canvas = game.display_balls_and_bricks(canvas);
             },
             _ => {
-                canvas = game.display_menu(canvas);
+                canvas = game.display_screen(canvas);
             }
         }
         frame = frame + 1;
 
-        canvas.present();
+        // The GIF clip starts over on each launch and only keeps the round.
+        if game.round && !in_round {
+            clip.clear();
+        }
+        in_round = game.round;
+        let clip_frame = in_round && game.screen() == Screen::Playing && frame % CLIP_FRAME_STEP == 0;
+
+        if recorder.is_some() || clip_frame || screenshot {
+            match view.read(&canvas) {
+                Ok(mut pixels) => {
+                    if clip_frame {
+                        clip.push(&pixels);
+                    }
+                    if let Some(recording) = recorder.as_mut() {
+                        let samples = audio.mixdown(SAMPLES_PER_FRAME);
+                        if let Err(e) = recording.frame(&pixels, &samples) {
+                            error::log(&format!("recording stopped: {}", e));
+                            recorder = None;
+                            audio.capture(false);
+                        }
+                    }
+                    if screenshot {
+                        let path = capture::file_name("screenshot", game.wave.wave_number, game.replay.seed, "png");
+                        match capture::screenshot(&mut pixels, WINDOW_WIDTH, WINDOW_HEIGHT, &path) {
+                            Ok(()) => game.notify("notice-screenshot-saved", &path),
+                            Err(e) => error::log(&format!("cannot save {}: {}", path.display(), e))
+                        }
+                    }
+                },
+                Err(e) => error::log(&format!("cannot read the canvas: {}", e))
+            }
+            screenshot = false;
+        }
+
+        canvas = game.display_notice(canvas);
+        canvas = view.present(canvas);
+
+        if headless {
+            let finished = playback.as_ref().is_none_or(|replay| next_launch >= replay.launches.len());
+            if game.screen() == Screen::Lost || (finished && game.is_aiming()) {
+                break 'running;
+            }
+        }
+        else {
+            ::std::thread::sleep(Duration::from_millis(16));
+        }
+    }
 
-        ::std::thread::sleep(Duration::from_millis(16));
+    if let Some(recording) = recorder.take() {
+        finish_recording(recording);
     }
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,169 @@
+use rand::Rng;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+use std::f32::consts::PI;
+
+const MAX_PARTICLES: usize = 600;
+const LOW_EFFECTS_MAX_PARTICLES: usize = 120;
+// With reduced effects, each emitter only produces one particle out of LOW_EFFECTS_DIVISOR.
+const LOW_EFFECTS_DIVISOR: u32 = 4;
+const SHOCKWAVE_SEGMENTS: usize = 40;
+
+// Settings of an emission kind; ranges are drawn at random for each particle.
+pub struct Emitter {
+    pub count: u32,
+    pub speed: (f32, f32),
+    pub lifetime: (u32, u32),
+    pub size: (u32, u32),
+    pub gravity: f32,
+    pub drag: f32
+}
+
+// Debris of a broken brick, in its color.
+pub const DEBRIS: Emitter = Emitter {
+    count: 14,
+    speed: (1.0, 4.0),
+    lifetime: (25, 50),
+    size: (3, 6),
+    gravity: 0.15,
+    drag: 0.97
+};
+
+// Sparks where a ball hits.
+pub const SPARKS: Emitter = Emitter {
+    count: 5,
+    speed: (2.0, 5.0),
+    lifetime: (8, 16),
+    size: (2, 3),
+    gravity: 0.0,
+    drag: 0.85
+};
+
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    age: u32,
+    lifetime: u32,
+    size: u32,
+    gravity: f32,
+    drag: f32,
+    color: Color
+}
+
+// Bomb shockwave: a circle that grows up to the explosion radius.
+struct Shockwave {
+    center: Point,
+    radius: f32,
+    age: u32,
+    lifetime: u32
+}
+
+// Purely visual: the particles' randomness does not touch the game seed.
+pub struct Particles {
+    particles: Vec<Particle>,
+    shockwaves: Vec<Shockwave>,
+    pub low_effects: bool
+}
+
+impl Particles {
+    pub fn new(low_effects: bool) -> Self {
+        Particles {
+            particles: Vec::new(),
+            shockwaves: Vec::new(),
+            low_effects
+        }
+    }
+
+    fn budget(&self) -> usize {
+        if self.low_effects { LOW_EFFECTS_MAX_PARTICLES } else { MAX_PARTICLES }
+    }
+
+    // Past the budget, new particles are not created.
+    pub fn emit(&mut self, emitter: &Emitter, center: Point, color: Color) {
+        let count = if self.low_effects { emitter.count.div_ceil(LOW_EFFECTS_DIVISOR) } else { emitter.count };
+        let count = (count as usize).min(self.budget().saturating_sub(self.particles.len()));
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..count {
+            let direction = rng.gen_range(0.0..2.0 * PI);
+            let speed = rng.gen_range(emitter.speed.0..=emitter.speed.1);
+            self.particles.push(Particle {
+                x: center.x() as f32,
+                y: center.y() as f32,
+                vx: direction.cos() * speed,
+                vy: direction.sin() * speed,
+                age: 0,
+                lifetime: rng.gen_range(emitter.lifetime.0..=emitter.lifetime.1),
+                size: rng.gen_range(emitter.size.0..=emitter.size.1),
+                gravity: emitter.gravity,
+                drag: emitter.drag,
+                color
+            });
+        }
+    }
+
+    pub fn shockwave(&mut self, center: Point, radius: u32) {
+        self.shockwaves.push(Shockwave {
+            center,
+            radius: radius as f32,
+            age: 0,
+            lifetime: if self.low_effects { 10 } else { 20 }
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.shockwaves.clear();
+    }
+
+    pub fn update(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.vx *= particle.drag;
+            particle.vy = particle.vy * particle.drag + particle.gravity;
+            particle.x += particle.vx;
+            particle.y += particle.vy;
+            particle.age += 1;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+
+        for shockwave in self.shockwaves.iter_mut() {
+            shockwave.age += 1;
+        }
+        self.shockwaves.retain(|shockwave| shockwave.age < shockwave.lifetime);
+    }
+
+    // Particles fade out at the end of their life; the shockwave slows down near its radius.
+    pub fn display(&self, mut can: Canvas<Window>) -> Canvas<Window> {
+        can.set_blend_mode(BlendMode::Blend);
+
+        for particle in self.particles.iter() {
+            let alpha = 255 - 255 * particle.age / particle.lifetime;
+            can.set_draw_color(Color::RGBA(particle.color.r, particle.color.g, particle.color.b, alpha as u8));
+            let half = particle.size as i32 / 2;
+            let _ = can.fill_rect(Rect::new(particle.x as i32 - half, particle.y as i32 - half, particle.size, particle.size));
+        }
+
+        for shockwave in self.shockwaves.iter() {
+            let progress = shockwave.age as f32 / shockwave.lifetime as f32;
+            let radius = shockwave.radius * (1.0 - (1.0 - progress).powi(2));
+            let alpha = (255.0 * (1.0 - progress)) as u8;
+            can.set_draw_color(Color::RGBA(255, 200, 120, alpha));
+
+            let points: Vec<Point> = (0..=SHOCKWAVE_SEGMENTS).map(|i| {
+                let angle = i as f32 * 2.0 * PI / SHOCKWAVE_SEGMENTS as f32;
+                Point::new(
+                    shockwave.center.x() + (angle.cos() * radius) as i32,
+                    shockwave.center.y() + (angle.sin() * radius) as i32
+                )
+            }).collect();
+            let _ = can.draw_lines(points.as_slice());
+        }
+
+        can.set_blend_mode(BlendMode::None);
+        can
+    }
+}
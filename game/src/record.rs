@@ -0,0 +1,174 @@
+use ffmpeg_next::{codec, encoder, format, frame, ChannelLayout, Packet, Rational};
+use ffmpeg_next::format::sample::Type as SampleType;
+use ffmpeg_next::software::scaling;
+use std::path::{Path, PathBuf};
+
+use crate::audio::{CHANNELS, SAMPLE_RATE};
+use crate::error::GameError;
+
+pub const FPS: i32 = 60;
+pub const SAMPLES_PER_FRAME: usize = (SAMPLE_RATE / FPS) as usize;
+const AUDIO_BIT_RATE: usize = 128_000;
+
+// Encodes the canvas frames as H.264 and the game mix as AAC into a file
+// (MP4 depending on the extension). The video time follows the frames, not the clock:
+// one image per game frame, at FPS images per second, even without a window.
+pub struct Recorder {
+    path: PathBuf,
+    output: format::context::Output,
+    video: encoder::video::Encoder,
+    audio: Option<encoder::audio::Encoder>,
+    video_index: usize,
+    audio_index: usize,
+    scaler: scaling::Context,
+    rgb: frame::Video,
+    yuv: frame::Video,
+    width: u32,
+    height: u32,
+    frames: i64,
+    samples: Vec<i16>,
+    audio_pts: i64
+}
+
+impl Recorder {
+    pub fn start(path: &Path, width: u32, height: u32) -> Result<Self, GameError> {
+        ffmpeg_next::init()?;
+        let mut output = format::output(&path)?;
+        let global_header = output.format().flags().contains(format::Flags::GLOBAL_HEADER);
+
+        let h264 = encoder::find(codec::Id::H264)
+            .ok_or_else(|| GameError::Record(String::from("no H.264 encoder available")))?;
+        let mut stream = output.add_stream(h264)?;
+        let video_index = stream.index();
+        let mut video = stream.codec().encoder().video()?;
+        video.set_width(width);
+        video.set_height(height);
+        video.set_format(format::Pixel::YUV420P);
+        video.set_time_base((1, FPS));
+        video.set_frame_rate(Some((FPS, 1)));
+        if global_header {
+            video.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+        let video = video.open_as(h264)?;
+        stream.set_time_base((1, FPS));
+        stream.set_parameters(&video);
+
+        // Without an AAC encoder, the video is recorded without sound.
+        let (audio, audio_index) = match encoder::find(codec::Id::AAC) {
+            Some(aac) => {
+                let mut stream = output.add_stream(aac)?;
+                let audio_index = stream.index();
+                let mut audio = stream.codec().encoder().audio()?;
+                audio.set_rate(SAMPLE_RATE);
+                audio.set_channel_layout(ChannelLayout::STEREO);
+                audio.set_channels(CHANNELS);
+                audio.set_format(format::Sample::F32(SampleType::Planar));
+                audio.set_bit_rate(AUDIO_BIT_RATE);
+                audio.set_time_base((1, SAMPLE_RATE));
+                if global_header {
+                    audio.set_flags(codec::Flags::GLOBAL_HEADER);
+                }
+                let audio = audio.open_as(aac)?;
+                stream.set_time_base((1, SAMPLE_RATE));
+                stream.set_parameters(&audio);
+                (Some(audio), audio_index)
+            },
+            None => {
+                crate::error::log("no AAC encoder available, recording without sound");
+                (None, 0)
+            }
+        };
+
+        output.write_header()?;
+
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            output,
+            video,
+            audio,
+            video_index,
+            audio_index,
+            scaler: scaling::Context::get(format::Pixel::RGB24, width, height, format::Pixel::YUV420P, width, height, scaling::Flags::BILINEAR)?,
+            rgb: frame::Video::new(format::Pixel::RGB24, width, height),
+            yuv: frame::Video::new(format::Pixel::YUV420P, width, height),
+            width,
+            height,
+            frames: 0,
+            samples: Vec::new(),
+            audio_pts: 0
+        })
+    }
+
+    // `pixels`: RGB24 image of the canvas, contiguous rows; `samples`: interleaved stereo
+    // mix matching this image.
+    pub fn frame(&mut self, pixels: &[u8], samples: &[i16]) -> Result<(), GameError> {
+        let row = self.width as usize * 3;
+        let stride = self.rgb.stride(0);
+        let data = self.rgb.data_mut(0);
+        for (y, line) in pixels.chunks_exact(row).take(self.height as usize).enumerate() {
+            data[y * stride..y * stride + row].copy_from_slice(line);
+        }
+
+        self.scaler.run(&self.rgb, &mut self.yuv)?;
+        self.yuv.set_pts(Some(self.frames));
+        self.frames += 1;
+        self.video.send_frame(&self.yuv)?;
+        Recorder::write_packets(&mut self.video, &mut self.output, self.video_index, Rational(1, FPS))?;
+
+        self.samples.extend_from_slice(samples);
+        self.encode_audio(false)
+    }
+
+    // The AAC encoder expects fixed-size blocks; the rest waits for the next image,
+    // or is padded with silence at the end.
+    fn encode_audio(&mut self, flush: bool) -> Result<(), GameError> {
+        let audio = match self.audio.as_mut() {
+            Some(audio) => audio,
+            None => return Ok(())
+        };
+        let size = audio.frame_size() as usize;
+        let channels = CHANNELS as usize;
+
+        while self.samples.len() >= size * channels || (flush && !self.samples.is_empty()) {
+            let block: Vec<i16> = self.samples.drain(..(size * channels).min(self.samples.len())).collect();
+            let mut samples = frame::Audio::new(format::Sample::F32(SampleType::Planar), size, ChannelLayout::STEREO);
+            samples.set_rate(SAMPLE_RATE as u32);
+            for channel in 0..channels {
+                let plane = samples.plane_mut::<f32>(channel);
+                for (i, sample) in plane.iter_mut().enumerate() {
+                    *sample = block.get(i * channels + channel).map_or(0.0, |value| *value as f32 / 32_768.0);
+                }
+            }
+            samples.set_pts(Some(self.audio_pts));
+            self.audio_pts += size as i64;
+
+            audio.send_frame(&samples)?;
+            Recorder::write_packets(audio, &mut self.output, self.audio_index, Rational(1, SAMPLE_RATE))?;
+        }
+        Ok(())
+    }
+
+    fn write_packets(encoder: &mut encoder::Encoder, output: &mut format::context::Output, index: usize, time_base: Rational) -> Result<(), GameError> {
+        let stream_time_base = output.stream(index).map(|stream| stream.time_base()).unwrap_or(time_base);
+        let mut packet = Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(index);
+            packet.rescale_ts(time_base, stream_time_base);
+            packet.write_interleaved(output)?;
+        }
+        Ok(())
+    }
+
+    // Flushes the encoders and finishes the file; returns its path.
+    pub fn finish(mut self) -> Result<PathBuf, GameError> {
+        self.encode_audio(true)?;
+        if let Some(audio) = self.audio.as_mut() {
+            audio.send_eof()?;
+            Recorder::write_packets(audio, &mut self.output, self.audio_index, Rational(1, SAMPLE_RATE))?;
+        }
+        self.video.send_eof()?;
+        Recorder::write_packets(&mut self.video, &mut self.output, self.video_index, Rational(1, FPS))?;
+        self.output.write_trailer()?;
+        Ok(self.path)
+    }
+}
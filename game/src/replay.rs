@@ -0,0 +1,147 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::error::GameError;
+use crate::score::DEFAULT_MODE;
+
+pub const LAST_REPLAY_PATH: &str = "last_game.replay";
+
+// A game is entirely determined by the wave seed and the shots (its score also depends
+// on the scoring mode):
+// the simulation tick at which the round is launched and the aim angle at that moment,
+// plus the ticks at which balls were recalled.
+#[derive(Clone, Copy)]
+pub struct Launch {
+    pub tick: u64,
+    pub angle: f64
+}
+
+pub struct Replay {
+    pub seed: u64,
+    pub mode: String,
+    pub launches: Vec<Launch>,
+    pub recalls: Vec<u64>
+}
+
+impl Replay {
+    pub fn new(seed: u64, mode: &str) -> Self {
+        Replay {
+            seed,
+            mode: String::from(mode),
+            launches: Vec::new(),
+            recalls: Vec::new()
+        }
+    }
+
+    // Text format: a `seed <seed>` line, an optional `mode <mode>` line (classic when
+    // missing), then one `launch <tick> <angle>` line per shot and one `recall <tick>`
+    // line per recall.
+    pub fn load(path: &Path) -> Result<Self, GameError> {
+        let content = fs::read_to_string(path)?;
+        let invalid = |number: usize| GameError::Assets(format!("{}:{}: invalid replay line", path.display(), number + 1));
+
+        let mut replay: Option<Replay> = None;
+        for (number, line) in content.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words.as_slice(), replay.as_mut()) {
+                ([], _) => {},
+                (["seed", seed], None) => {
+                    replay = Some(Replay::new(seed.parse().map_err(|_| invalid(number))?, DEFAULT_MODE));
+                },
+                (["mode", mode], Some(replay)) => replay.mode = String::from(*mode),
+                (["launch", tick, angle], Some(replay)) => {
+                    replay.launches.push(Launch {
+                        tick: tick.parse().map_err(|_| invalid(number))?,
+                        angle: angle.parse().map_err(|_| invalid(number))?
+                    });
+                },
+                (["recall", tick], Some(replay)) => {
+                    replay.recalls.push(tick.parse().map_err(|_| invalid(number))?);
+                },
+                _ => return Err(invalid(number))
+            }
+        }
+        replay.ok_or_else(|| GameError::Assets(format!("{}: missing seed line", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = format!("seed {}\nmode {}\n", self.seed, self.mode);
+        for launch in self.launches.iter() {
+            content.push_str(&format!("launch {} {}\n", launch.tick, launch.angle));
+        }
+        for tick in self.recalls.iter() {
+            content.push_str(&format!("recall {}\n", tick));
+        }
+        fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.replay", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_then_load_gives_the_same_replay() {
+        let mut replay = Replay::new(u64::MAX - 7, "arcade");
+        replay.launches.push(Launch { tick: 0, angle: std::f64::consts::PI / 3.0 });
+        replay.launches.push(Launch { tick: 4_321, angle: 0.1 + 0.2 });
+        replay.launches.push(Launch { tick: 99_999, angle: 2.879_793_265_790_644 });
+        replay.recalls.push(1_000);
+        replay.recalls.push(123_456);
+
+        let path = temp_path("round-trip");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.ok().unwrap();
+
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.mode, replay.mode);
+        assert_eq!(loaded.recalls, replay.recalls);
+        assert_eq!(loaded.launches.len(), replay.launches.len());
+        for (loaded, launch) in loaded.launches.iter().zip(replay.launches.iter()) {
+            assert_eq!(loaded.tick, launch.tick);
+            // The simulation must see exactly the angle that was played.
+            assert_eq!(loaded.angle.to_bits(), launch.angle.to_bits());
+        }
+    }
+
+    #[test]
+    fn replay_without_mode_is_classic() {
+        let path = temp_path("no-mode");
+        fs::write(&path, "seed 3\nlaunch 10 1.5\n").unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.ok().unwrap().mode, DEFAULT_MODE);
+    }
+
+    #[test]
+    fn load_rejects_invalid_lines() {
+        let cases = [
+            "launch 10 1.5\n",
+            "seed 1\nseed 2\n",
+            "seed x\n",
+            "seed 1\nlaunch 10\n",
+            "seed 1\nlaunch -1 1.5\n",
+            "seed 1\nlaunch 10 left\n",
+            "seed 1\nrecall\n",
+            "seed 1\njump 10\n",
+            "mode arcade\nseed 1\n",
+            "seed 1\nmode\n",
+            ""
+        ];
+        for (i, content) in cases.iter().enumerate() {
+            let path = temp_path(&format!("invalid-{}", i));
+            fs::write(&path, content).unwrap();
+            let loaded = Replay::load(&path);
+            fs::remove_file(&path).unwrap();
+            assert!(loaded.is_err(), "accepted {:?}", content);
+        }
+    }
+}
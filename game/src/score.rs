@@ -0,0 +1,341 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{self, GameError};
+
+pub const MODES_DIR: &str = "modes";
+pub const MODE_FILE_EXTENSION: &str = "txt";
+pub const DEFAULT_MODE: &str = "classic";
+pub const HIGH_SCORES_PATH: &str = "highscores.txt";
+const HIGH_SCORES_KEPT: usize = 10;
+
+// Scoring rules of a game mode, read from modes/<mode>.txt as `key = value` lines.
+// Any missing key keeps the classic value.
+#[derive(Clone)]
+pub struct ScoreRules {
+    pub mode: String,
+    // Points per hit point of damage dealt.
+    pub damage_points: u32,
+    pub brick_points: u32,
+    // Multiplied by the number of the wave cleared.
+    pub wave_bonus: u32,
+    // The multiplier goes up by one every `combo_step` bricks broken in the same round.
+    pub combo_step: u32,
+    pub max_multiplier: u32,
+    // The n-th bonus brick broken in a round earns n times this.
+    pub chain_bonus: u32
+}
+
+impl ScoreRules {
+    pub fn classic() -> Self {
+        ScoreRules {
+            mode: String::from(DEFAULT_MODE),
+            damage_points: 1,
+            brick_points: 10,
+            wave_bonus: 100,
+            combo_step: 5,
+            max_multiplier: 5,
+            chain_bonus: 50
+        }
+    }
+
+    pub fn load(root: &Path, mode: &str) -> Result<Self, GameError> {
+        let path = root.join(mode).with_extension(MODE_FILE_EXTENSION);
+        let mut rules = ScoreRules::classic();
+        rules.mode = String::from(mode);
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) if mode == DEFAULT_MODE => return Ok(rules),
+            Err(e) => return Err(GameError::Assets(format!("cannot read mode {}: {}", path.display(), e)))
+        };
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |message: &str| GameError::Assets(format!("{}:{}: {}", path.display(), number + 1, message));
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected `key = value`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let value = value.parse::<u32>().map_err(|_| invalid("expected a positive number"))?;
+
+            match key {
+                "damage_points" => rules.damage_points = value,
+                "brick_points" => rules.brick_points = value,
+                "wave_bonus" => rules.wave_bonus = value,
+                "combo_step" => rules.combo_step = value.max(1),
+                "max_multiplier" => rules.max_multiplier = value.max(1),
+                "chain_bonus" => rules.chain_bonus = value,
+                _ => error::log(&format!("{}:{}: unknown scoring key `{}`", path.display(), number + 1, key))
+            }
+        }
+        Ok(rules)
+    }
+}
+
+// Score of the game in progress. Everything is counted from simulation events, so a
+// replay scores exactly like the game it records.
+pub struct Score {
+    pub rules: ScoreRules,
+    pub points: u32,
+    pub bricks: u32,
+    pub best_combo: u32,
+    round_bricks: u32,
+    chain: u32
+}
+
+impl Score {
+    pub fn new(rules: ScoreRules) -> Self {
+        Score {
+            rules,
+            points: 0,
+            bricks: 0,
+            best_combo: 0,
+            round_bricks: 0,
+            chain: 0
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Score::new(self.rules.clone());
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.round_bricks / self.rules.combo_step).min(self.rules.max_multiplier)
+    }
+
+    pub fn damage(&mut self, hit_points: u32) {
+        self.points += hit_points * self.rules.damage_points * self.multiplier();
+    }
+
+    pub fn brick_broken(&mut self, bonus: bool) {
+        self.points += self.rules.brick_points * self.multiplier();
+        if bonus {
+            self.chain += 1;
+            self.points += self.rules.chain_bonus * self.chain;
+        }
+        self.bricks += 1;
+        self.round_bricks += 1;
+        self.best_combo = self.best_combo.max(self.round_bricks);
+    }
+
+    pub fn round_over(&mut self) {
+        self.round_bricks = 0;
+        self.chain = 0;
+    }
+
+    pub fn wave_cleared(&mut self, wave_number: u32) {
+        self.points += self.rules.wave_bonus * wave_number;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct HighScore {
+    pub points: u32,
+    pub wave: u32,
+    pub best_combo: u32
+}
+
+// The best HIGH_SCORES_KEPT games of each mode, one `<mode> <points> <wave> <best combo>`
+// line per game.
+pub struct HighScores {
+    entries: Vec<(String, HighScore)>
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        let content = fs::read_to_string(HIGH_SCORES_PATH).unwrap_or_default();
+        let mut entries = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match words.as_slice() {
+                [mode, points, wave, best_combo] => match (points.parse(), wave.parse(), best_combo.parse()) {
+                    (Ok(points), Ok(wave), Ok(best_combo)) => Some((String::from(*mode), HighScore { points, wave, best_combo })),
+                    _ => None
+                },
+                _ => None
+            };
+            match parsed {
+                Some(entry) => entries.push(entry),
+                None => error::log(&format!("{}:{}: invalid high score line", HIGH_SCORES_PATH, number + 1))
+            }
+        }
+        HighScores { entries }
+    }
+
+    // Best first.
+    pub fn top(&self, mode: &str) -> Vec<HighScore> {
+        let mut scores: Vec<HighScore> = self.entries.iter().filter(|(name, _)| name == mode).map(|(_, score)| *score).collect();
+        scores.sort_by(|a, b| b.points.cmp(&a.points).then(b.wave.cmp(&a.wave)));
+        scores.truncate(HIGH_SCORES_KEPT);
+        scores
+    }
+
+    pub fn best(&self, mode: &str) -> Option<HighScore> {
+        self.top(mode).first().copied()
+    }
+
+    // Returns the rank of the game (0 for the best) if it made the table.
+    pub fn record(&mut self, mode: &str, score: HighScore) -> Option<usize> {
+        let top = self.top(mode);
+        let rank = top.iter().position(|other| score.points > other.points).unwrap_or(top.len());
+        if rank >= HIGH_SCORES_KEPT {
+            return None;
+        }
+
+        self.entries.retain(|(name, _)| name != mode);
+        let mut top = top;
+        top.insert(rank, score);
+        top.truncate(HIGH_SCORES_KEPT);
+        self.entries.extend(top.into_iter().map(|score| (String::from(mode), score)));
+        self.save();
+        Some(rank)
+    }
+
+    fn save(&self) {
+        let mut content = String::new();
+        for (mode, score) in self.entries.iter() {
+            content.push_str(&format!("{} {} {} {}\n", mode, score.points, score.wave, score.best_combo));
+        }
+        if let Err(e) = fs::write(HIGH_SCORES_PATH, content) {
+            error::log(&format!("cannot save {}: {}", HIGH_SCORES_PATH, e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rules() -> ScoreRules {
+        ScoreRules {
+            mode: String::from("test"),
+            damage_points: 2,
+            brick_points: 10,
+            wave_bonus: 100,
+            combo_step: 2,
+            max_multiplier: 3,
+            chain_bonus: 50
+        }
+    }
+
+    // A modes folder holding one `test` mode with the given content.
+    fn modes_root(name: &str, content: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("modes-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("test").with_extension(MODE_FILE_EXTENSION), content).unwrap();
+        root
+    }
+
+    #[test]
+    fn multiplier_grows_with_the_bricks_of_the_round() {
+        let mut score = Score::new(rules());
+        assert_eq!(score.multiplier(), 1);
+        score.brick_broken(false);
+        assert_eq!(score.multiplier(), 1);
+        score.brick_broken(false);
+        assert_eq!(score.multiplier(), 2);
+        for _ in 0..10 {
+            score.brick_broken(false);
+        }
+        assert_eq!(score.multiplier(), 3);
+        assert_eq!(score.best_combo, 12);
+
+        score.round_over();
+        assert_eq!(score.multiplier(), 1);
+        assert_eq!(score.best_combo, 12);
+        assert_eq!(score.bricks, 12);
+    }
+
+    #[test]
+    fn bricks_and_damage_are_multiplied() {
+        let mut score = Score::new(rules());
+        score.damage(5);
+        assert_eq!(score.points, 10);
+        score.brick_broken(false);
+        score.brick_broken(false);
+        assert_eq!(score.points, 30);
+        score.damage(5);
+        assert_eq!(score.points, 50);
+    }
+
+    #[test]
+    fn bonus_bricks_chain_until_the_round_is_over() {
+        let mut score = Score::new(rules());
+        score.brick_broken(true);
+        assert_eq!(score.points, 10 + 50);
+        score.brick_broken(true);
+        assert_eq!(score.points, 60 + 10 + 100);
+        score.brick_broken(false);
+        assert_eq!(score.points, 170 + 20);
+
+        score.round_over();
+        score.brick_broken(true);
+        assert_eq!(score.points, 190 + 10 + 50);
+    }
+
+    #[test]
+    fn wave_bonus_grows_with_the_wave() {
+        let mut score = Score::new(rules());
+        score.wave_cleared(1);
+        score.wave_cleared(3);
+        assert_eq!(score.points, 400);
+
+        score.reset();
+        assert_eq!(score.points, 0);
+        assert_eq!(score.rules.mode, "test");
+    }
+
+    #[test]
+    fn rules_file_overrides_classic_values() {
+        let root = modes_root("valid", "# comment\n\nbrick_points = 20\n  combo_step=0\nmax_multiplier = 7\n");
+        let loaded = ScoreRules::load(&root, "test");
+        fs::remove_dir_all(&root).unwrap();
+        let loaded = loaded.ok().unwrap();
+
+        let classic = ScoreRules::classic();
+        assert_eq!(loaded.mode, "test");
+        assert_eq!(loaded.brick_points, 20);
+        assert_eq!(loaded.combo_step, 1);
+        assert_eq!(loaded.max_multiplier, 7);
+        assert_eq!(loaded.damage_points, classic.damage_points);
+        assert_eq!(loaded.chain_bonus, classic.chain_bonus);
+    }
+
+    #[test]
+    fn rules_file_rejects_malformed_lines() {
+        for (i, content) in ["brick_points 20\n", "brick_points = -3\n", "wave_bonus = lots\n", "chain_bonus =\n"].iter().enumerate() {
+            let root = modes_root(&format!("invalid-{}", i), content);
+            let loaded = ScoreRules::load(&root, "test");
+            fs::remove_dir_all(&root).unwrap();
+            assert!(loaded.is_err(), "accepted {:?}", content);
+        }
+    }
+
+    #[test]
+    fn missing_mode_file() {
+        let root = std::env::temp_dir().join(format!("modes-missing-{}", std::process::id()));
+        assert!(ScoreRules::load(&root, "test").is_err());
+        assert_eq!(ScoreRules::load(&root, DEFAULT_MODE).ok().unwrap().brick_points, ScoreRules::classic().brick_points);
+    }
+
+    #[test]
+    fn high_scores_are_sorted_per_mode() {
+        let score = |points, wave| HighScore { points, wave, best_combo: 0 };
+        let high_scores = HighScores {
+            entries: vec![
+                (String::from("classic"), score(100, 2)),
+                (String::from("arcade"), score(900, 9)),
+                (String::from("classic"), score(300, 4)),
+                (String::from("classic"), score(300, 5))
+            ]
+        };
+        let top: Vec<(u32, u32)> = high_scores.top("classic").iter().map(|score| (score.points, score.wave)).collect();
+        assert_eq!(top, vec![(300, 5), (300, 4), (100, 2)]);
+        assert_eq!(high_scores.best("arcade").map(|score| score.points), Some(900));
+        assert!(high_scores.best("other").is_none());
+    }
+}
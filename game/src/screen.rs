@@ -0,0 +1,49 @@
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Screen {
+    Menu,
+    Playing,
+    Paused,
+    Lost,
+    Settings,
+    Audio,
+    Display,
+    Credits,
+    Shop,
+    Editor
+}
+
+// Screen stack: secondary menus and pause are pushed above the screen
+// that opened them, and "back" pops.
+pub struct ScreenStack {
+    stack: Vec<Screen>
+}
+
+impl ScreenStack {
+    pub fn new() -> Self {
+        ScreenStack {
+            stack: vec![Screen::Menu]
+        }
+    }
+
+    pub fn current(&self) -> Screen {
+        *self.stack.last().unwrap()
+    }
+
+    pub fn push(&mut self, screen: Screen) {
+        self.stack.push(screen);
+    }
+
+    pub fn pop(&mut self) -> Option<Screen> {
+        if self.stack.len() > 1 {
+            self.stack.pop()
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn reset(&mut self, screen: Screen) {
+        self.stack.clear();
+        self.stack.push(screen);
+    }
+}
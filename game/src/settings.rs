@@ -0,0 +1,231 @@
+use sdl2::keyboard::Keycode;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+
+use crate::audio::MAX_VOLUME;
+use crate::error;
+use crate::input::{Action, Bindings};
+use crate::locale::Locale;
+use crate::score::DEFAULT_MODE;
+use crate::theme::{ColorPreset, DEFAULT_THEME};
+
+pub const SETTINGS_PATH: &str = "settings.cfg";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AssistLevel {
+    Low,
+    Medium,
+    High
+}
+
+impl AssistLevel {
+    pub const ALL: [AssistLevel; 3] = [AssistLevel::Low, AssistLevel::Medium, AssistLevel::High];
+
+    pub fn index(&self) -> i32 {
+        AssistLevel::ALL.iter().position(|level| level == self).unwrap() as i32
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssistLevel::Low => "low",
+            AssistLevel::Medium => "medium",
+            AssistLevel::High => "high"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(AssistLevel::Low),
+            "medium" => Some(AssistLevel::Medium),
+            "high" => Some(AssistLevel::High),
+            _ => None
+        }
+    }
+
+    // Low stops at the first contact, Medium goes through wall bounces
+    // up to the first brick, High goes up to the second brick.
+    pub fn wall_bounces(&self) -> bool {
+        *self != AssistLevel::Low
+    }
+
+    pub fn brick_contacts(&self) -> u32 {
+        match self {
+            AssistLevel::Low | AssistLevel::Medium => 1,
+            AssistLevel::High => 2
+        }
+    }
+}
+
+pub struct Settings {
+    pub trajectory_preview: bool,
+    pub assist_level: AssistLevel,
+    pub master_volume: i32,
+    pub music_volume: i32,
+    pub sfx_volume: i32,
+    pub muted: bool,
+    pub low_effects: bool,
+    pub screen_shake: bool,
+    pub hit_stop: bool,
+    pub ball_trails: bool,
+    pub reduce_motion: bool,
+    pub fullscreen: bool,
+    pub theme: String,
+    pub color_preset: ColorPreset,
+    pub brick_patterns: bool,
+    pub language: String,
+    // Scoring rules, from modes/<mode>.txt.
+    pub mode: String,
+    pub bindings: Bindings
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            trajectory_preview: true,
+            assist_level: AssistLevel::Medium,
+            master_volume: MAX_VOLUME,
+            music_volume: MAX_VOLUME,
+            sfx_volume: MAX_VOLUME,
+            muted: false,
+            low_effects: false,
+            screen_shake: true,
+            hit_stop: true,
+            ball_trails: true,
+            reduce_motion: false,
+            fullscreen: false,
+            theme: String::from(DEFAULT_THEME),
+            color_preset: ColorPreset::Theme,
+            brick_patterns: false,
+            language: Locale::system_language(),
+            mode: String::from(DEFAULT_MODE),
+            bindings: Bindings::defaults()
+        }
+    }
+
+    pub fn load() -> Self {
+        let mut settings = Settings::new();
+
+        let file = match File::open(SETTINGS_PATH) {
+            Ok(file) => file,
+            Err(_) => return settings
+        };
+
+        let mut overrides: Vec<(Action, Keycode)> = Vec::new();
+
+        for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue
+            };
+
+            match key {
+                "trajectory_preview" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        settings.trajectory_preview = enabled;
+                    }
+                },
+                "assist_level" => {
+                    if let Some(level) = AssistLevel::from_name(value) {
+                        settings.assist_level = level;
+                    }
+                },
+                "master_volume" | "music_volume" | "sfx_volume" => {
+                    if let Ok(volume) = value.parse::<i32>() {
+                        let volume = volume.clamp(0, MAX_VOLUME);
+                        match key {
+                            "master_volume" => settings.master_volume = volume,
+                            "music_volume" => settings.music_volume = volume,
+                            _ => settings.sfx_volume = volume
+                        }
+                    }
+                },
+                "muted" => {
+                    if let Ok(muted) = value.parse::<bool>() {
+                        settings.muted = muted;
+                    }
+                },
+                "theme" if !value.is_empty() => settings.theme = String::from(value),
+                "language" if !value.is_empty() => settings.language = String::from(value),
+                "mode" if !value.is_empty() => settings.mode = String::from(value),
+                "color_preset" => {
+                    if let Some(preset) = ColorPreset::from_name(value) {
+                        settings.color_preset = preset;
+                    }
+                },
+                "low_effects" | "screen_shake" | "hit_stop" | "ball_trails" | "reduce_motion" | "fullscreen" | "brick_patterns" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        match key {
+                            "low_effects" => settings.low_effects = enabled,
+                            "screen_shake" => settings.screen_shake = enabled,
+                            "hit_stop" => settings.hit_stop = enabled,
+                            "ball_trails" => settings.ball_trails = enabled,
+                            "reduce_motion" => settings.reduce_motion = enabled,
+                            "brick_patterns" => settings.brick_patterns = enabled,
+                            _ => settings.fullscreen = enabled
+                        }
+                    }
+                },
+                _ => {
+                    let action = key.strip_prefix("bind.").and_then(Action::from_name);
+                    if let (Some(action), Some(keycode)) = (action, Keycode::from_name(value)) {
+                        overrides.push((action, keycode));
+                    }
+                }
+            }
+        }
+
+        let bindings = Bindings::with_overrides(&overrides);
+        let conflicts = bindings.conflicts();
+        if conflicts.is_empty() {
+            settings.bindings = bindings;
+        }
+        else {
+            for (key, action, other) in conflicts {
+                error::log(&format!("{}: {} is bound to both {} and {}, using default bindings", SETTINGS_PATH, key.name(), action.name(), other.name()));
+            }
+        }
+        settings
+    }
+
+    // "Reduce motion" turns off shakes, hit-stops and trails,
+    // whatever their own settings.
+    pub fn shake_enabled(&self) -> bool {
+        self.screen_shake && !self.reduce_motion
+    }
+
+    pub fn hit_stop_enabled(&self) -> bool {
+        self.hit_stop && !self.reduce_motion
+    }
+
+    pub fn trails_enabled(&self) -> bool {
+        self.ball_trails && !self.reduce_motion
+    }
+
+    pub fn save(&self) {
+        let mut content = String::new();
+        content.push_str(&format!("trajectory_preview={}\n", self.trajectory_preview));
+        content.push_str(&format!("assist_level={}\n", self.assist_level.name()));
+        content.push_str(&format!("master_volume={}\n", self.master_volume));
+        content.push_str(&format!("music_volume={}\n", self.music_volume));
+        content.push_str(&format!("sfx_volume={}\n", self.sfx_volume));
+        content.push_str(&format!("muted={}\n", self.muted));
+        content.push_str(&format!("low_effects={}\n", self.low_effects));
+        content.push_str(&format!("screen_shake={}\n", self.screen_shake));
+        content.push_str(&format!("hit_stop={}\n", self.hit_stop));
+        content.push_str(&format!("ball_trails={}\n", self.ball_trails));
+        content.push_str(&format!("reduce_motion={}\n", self.reduce_motion));
+        content.push_str(&format!("fullscreen={}\n", self.fullscreen));
+        content.push_str(&format!("theme={}\n", self.theme));
+        content.push_str(&format!("color_preset={}\n", self.color_preset.name()));
+        content.push_str(&format!("brick_patterns={}\n", self.brick_patterns));
+        content.push_str(&format!("language={}\n", self.language));
+        content.push_str(&format!("mode={}\n", self.mode));
+        for (action, key) in self.bindings.entries() {
+            content.push_str(&format!("bind.{}={}\n", action.name(), key.name()));
+        }
+
+        if let Ok(mut file) = File::create(SETTINGS_PATH) {
+            let _ = file.write_all(content.as_bytes());
+        }
+    }
+}
@@ -5,17 +5,17 @@ mod utils;
 use crate::utils::Brick;
 
 fn open_file() -> Vec<Brick> {
-    // Spécifie le chemin du fichier
+    // File path
     let path = "levels/test.txt";
 
-    // Ouvre le fichier
+    // Open the file
     let file = File::open(path).unwrap();
 
-    // Crée un buffer pour lire le fichier ligne par ligne
+    // Buffer to read the file line by line
     let reader = io::BufReader::new(file);
 
     let mut bricks:Vec<Brick> = Vec::new();
-    // Lit et affiche chaque ligne
+    // Read and print each line
     let mut ligne= 0;
     for line in reader.lines() {
         match line {
@@ -0,0 +1,185 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator, TextureQuery};
+use sdl2::surface::Surface;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::{Window, WindowContext};
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::error::{self, GameError};
+
+const FONT_SIZE: u16 = 128;
+const MAX_CACHED: usize = 256;
+const GLYPHS: &str = "0123456789-";
+const DARK_GLYPHS: Color = Color::RGB(30, 30, 30);
+const LIGHT_GLYPHS: Color = Color::RGB(245, 245, 245);
+const SYSTEM_FONTS: [&str; 5] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf"
+];
+
+// The fonts are loaded only once; each rendered text is cached by
+// (text, color). Brick hit points are drawn digit by digit
+// from an atlas rendered at startup, with no new surface during the game; the atlas
+// exists in dark and light digits to stay readable on every brick.
+pub struct Text<'a> {
+    fonts: Vec<Font<'a, 'static>>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    cache: HashMap<(String, Color), Rc<Texture<'a>>>,
+    atlas: Texture<'a>,
+    light_atlas: Texture<'a>,
+    glyphs: Vec<Rect>,
+    blank: Rc<Texture<'a>>
+}
+
+impl<'a> Text<'a> {
+    // The digit atlas is rendered with the first font of the chain.
+    pub fn new(font_paths: &[&Path], ttf_context: &'a Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) -> Result<Self, GameError> {
+        let fonts = Text::load_fonts(font_paths, ttf_context)?;
+        let font = &fonts[0];
+
+        let mut glyph_surfaces: Vec<Surface> = Vec::new();
+        for glyph in GLYPHS.chars() {
+            let mut surface = font.render_char(glyph).blended(Color::RGB(255, 255, 255)).map_err(|e| e.to_string())?;
+            surface.set_blend_mode(BlendMode::None)?;
+            glyph_surfaces.push(surface);
+        }
+
+        let width = glyph_surfaces.iter().map(|surface| surface.width()).sum();
+        let height = glyph_surfaces.iter().map(|surface| surface.height()).max().unwrap_or(1);
+        let mut atlas_surface = Surface::new(width, height, PixelFormatEnum::ARGB8888)?;
+
+        let mut glyphs: Vec<Rect> = Vec::new();
+        let mut x = 0;
+        for surface in glyph_surfaces.iter() {
+            let rect = Rect::new(x, 0, surface.width(), surface.height());
+            surface.blit(None, &mut atlas_surface, rect)?;
+            glyphs.push(rect);
+            x += surface.width() as i32;
+        }
+
+        let mut atlas = texture_creator.create_texture_from_surface(&atlas_surface).map_err(|e| e.to_string())?;
+        atlas.set_blend_mode(BlendMode::Blend);
+        atlas.set_color_mod(DARK_GLYPHS.r, DARK_GLYPHS.g, DARK_GLYPHS.b);
+        let mut light_atlas = texture_creator.create_texture_from_surface(&atlas_surface).map_err(|e| e.to_string())?;
+        light_atlas.set_blend_mode(BlendMode::Blend);
+        light_atlas.set_color_mod(LIGHT_GLYPHS.r, LIGHT_GLYPHS.g, LIGHT_GLYPHS.b);
+
+        let blank_surface = Surface::new(1, 1, PixelFormatEnum::ARGB8888)?;
+        let blank = Rc::new(texture_creator.create_texture_from_surface(&blank_surface).map_err(|e| e.to_string())?);
+
+        Ok(Text {
+            fonts,
+            texture_creator,
+            cache: HashMap::new(),
+            atlas,
+            light_atlas,
+            glyphs,
+            blank
+        })
+    }
+
+    // Every font that loads is kept, in order: a text is rendered with the first one that
+    // has all its glyphs (accents, symbols missing from the game font).
+    fn load_fonts(font_paths: &[&Path], ttf_context: &'a Sdl2TtfContext) -> Result<Vec<Font<'a, 'static>>, GameError> {
+        let system_fonts = SYSTEM_FONTS.iter().map(Path::new).filter(|path| path.is_file());
+        let mut fonts = Vec::new();
+        let mut failures: Vec<String> = Vec::new();
+
+        for path in font_paths.iter().copied().chain(system_fonts) {
+            match ttf_context.load_font(path, FONT_SIZE) {
+                Ok(font) => {
+                    if fonts.is_empty() && !failures.is_empty() {
+                        error::log(&format!("using fallback font {} ({})", path.display(), failures.join(", ")));
+                    }
+                    fonts.push(font);
+                },
+                Err(e) => failures.push(format!("{}: {}", path.display(), e))
+            }
+        }
+        if fonts.is_empty() {
+            return Err(GameError::Assets(format!("no usable font found:\n    {}", failures.join("\n    "))));
+        }
+        Ok(fonts)
+    }
+
+    fn font_for(&self, text: &str) -> &Font<'a, 'static> {
+        let has_glyphs = |font: &&Font| text.chars().all(|c| c.is_whitespace() || font.find_glyph(c).is_some());
+        self.fonts.iter().find(has_glyphs).unwrap_or(&self.fonts[0])
+    }
+
+    pub fn render(&mut self, text: &str, color: Color) -> Rc<Texture<'a>> {
+        let key = (String::from(text), color);
+        if let Some(texture) = self.cache.get(&key) {
+            return texture.clone();
+        }
+
+        // Only textures that nobody displays anymore are freed.
+        if self.cache.len() >= MAX_CACHED {
+            self.cache.retain(|_, texture| Rc::strong_count(texture) > 1);
+        }
+
+        // A text that cannot be rendered (empty, missing glyph) shows as an empty texture.
+        let texture = match self.font_for(text).render(text).blended(color) {
+            Ok(surface) => match self.texture_creator.create_texture_from_surface(&surface) {
+                Ok(texture) => Rc::new(texture),
+                Err(e) => {
+                    error::log(&format!("cannot create texture for \"{}\": {}", text, e));
+                    self.blank.clone()
+                }
+            },
+            Err(e) => {
+                error::log(&format!("cannot render \"{}\": {}", text, e));
+                self.blank.clone()
+            }
+        };
+
+        self.cache.insert(key, texture.clone());
+        texture
+    }
+
+    // Relative luminance (WCAG) of an sRGB color, from 0 (black) to 1 (white).
+    pub fn luminance(color: Color) -> f32 {
+        let linear = |channel: u8| {
+            let c = channel as f32 / 255.0;
+            if c <= 0.039_28 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+        0.2126 * linear(color.r) + 0.7152 * linear(color.g) + 0.0722 * linear(color.b)
+    }
+
+    // The digit shade that contrasts most with the background.
+    pub fn contrast_color(background: Color) -> Color {
+        let contrast = |color: Color| {
+            let (a, b) = (Text::luminance(color), Text::luminance(background));
+            (a.max(b) + 0.05) / (a.min(b) + 0.05)
+        };
+        if contrast(DARK_GLYPHS) >= contrast(LIGHT_GLYPHS) { DARK_GLYPHS } else { LIGHT_GLYPHS }
+    }
+
+    // Draws a number centered in the area, keeping the digits' proportions.
+    pub fn draw_number(&self, mut can: Canvas<Window>, value: i32, area: Rect, background: Color) -> Canvas<Window> {
+        let atlas = if Text::contrast_color(background) == DARK_GLYPHS { &self.atlas } else { &self.light_atlas };
+        let glyphs: Vec<Rect> = value.to_string().chars().filter_map(|c| GLYPHS.find(c)).map(|i| self.glyphs[i]).collect();
+        let TextureQuery { height, .. } = atlas.query();
+        let width: u32 = glyphs.iter().map(|glyph| glyph.width()).sum();
+        if width == 0 {
+            return can;
+        }
+
+        let scale = (area.width() as f32 / width as f32).min(area.height() as f32 / height as f32);
+        let mut x = area.x() as f32 + (area.width() as f32 - width as f32 * scale) / 2.0;
+        let y = area.y() + (area.height() as i32 - (height as f32 * scale) as i32) / 2;
+
+        for glyph in glyphs {
+            let w = glyph.width() as f32 * scale;
+            let _ = can.copy(atlas, glyph, Rect::new(x as i32, y, w.ceil() as u32, (glyph.height() as f32 * scale) as u32));
+            x += w;
+        }
+        can
+    }
+}
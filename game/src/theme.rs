@@ -0,0 +1,239 @@
+use sdl2::image::LoadTexture;
+use sdl2::pixels::Color;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::video::WindowContext;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{self, GameError};
+
+pub const THEMES_DIR: &str = "themes";
+pub const THEME_FILE: &str = "theme.txt";
+pub const DEFAULT_THEME: &str = "classic";
+pub const BRICK_KINDS: [&str; 5] = ["normal", "bomb", "more_balls", "more_damage", "bigger_balls"];
+
+// A theme is a folder of themes/ containing a theme.txt: colors as `#rrggbb`,
+// paths relative to the theme folder. Any missing key keeps the classic theme's value.
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    pub label: String,
+    pub background: Color,
+    pub wall: Color,
+    pub limit_bar: Color,
+    pub hud: Color,
+    pub title: Color,
+    bricks: HashMap<String, Color>,
+    pub font: Option<PathBuf>,
+    pub ball: Option<PathBuf>,
+    pub background_image: Option<PathBuf>,
+    brick_sprites: HashMap<String, PathBuf>
+}
+
+impl Theme {
+    // The game's original colors, used even without a themes/ folder.
+    pub fn classic() -> Self {
+        let bricks = [
+            ("normal", Color::RGB(255, 255, 255)),
+            ("bomb", Color::RGB(200, 50, 50)),
+            ("more_balls", Color::RGB(50, 200, 50)),
+            ("more_damage", Color::RGB(50, 50, 200)),
+            ("bigger_balls", Color::RGB(150, 150, 150))
+        ];
+
+        Theme {
+            name: String::from(DEFAULT_THEME),
+            label: String::from("Classic"),
+            background: Color::RGB(0, 0, 0),
+            wall: Color::RGB(50, 50, 255),
+            limit_bar: Color::RGB(255, 0, 0),
+            hud: Color::RGB(200, 200, 200),
+            title: Color::RGB(180, 120, 120),
+            bricks: bricks.iter().map(|(kind, color)| (String::from(*kind), *color)).collect(),
+            font: None,
+            ball: None,
+            background_image: None,
+            brick_sprites: HashMap::new()
+        }
+    }
+
+    // Names of the subfolders of `root` that contain a theme.txt, sorted, classic first.
+    pub fn discover(root: &Path) -> Vec<String> {
+        let mut names: Vec<String> = match fs::read_dir(root) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().join(THEME_FILE).is_file())
+                .filter_map(|entry| entry.file_name().to_str().map(String::from))
+                .filter(|name| name != DEFAULT_THEME)
+                .collect(),
+            Err(_) => Vec::new()
+        };
+        names.sort();
+        names.insert(0, String::from(DEFAULT_THEME));
+        names
+    }
+
+    pub fn load(root: &Path, name: &str) -> Result<Self, GameError> {
+        let dir = root.join(name);
+        let path = dir.join(THEME_FILE);
+        let mut theme = Theme::classic();
+        theme.name = String::from(name);
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) if name == DEFAULT_THEME => return Ok(theme),
+            Err(e) => return Err(GameError::Assets(format!("cannot read theme {}: {}", path.display(), e)))
+        };
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |message: &str| GameError::Assets(format!("{}:{}: {}", path.display(), number + 1, message));
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected `key = value`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let color = || Theme::parse_color(value).ok_or_else(|| invalid("expected a color like #ff8800"));
+
+            match key {
+                "name" => theme.label = String::from(value),
+                "background" => theme.background = color()?,
+                "wall" => theme.wall = color()?,
+                "limit_bar" => theme.limit_bar = color()?,
+                "hud" => theme.hud = color()?,
+                "title" => theme.title = color()?,
+                "font" => theme.font = Some(dir.join(value)),
+                "sprite.ball" => theme.ball = Some(dir.join(value)),
+                "sprite.background" => theme.background_image = Some(dir.join(value)),
+                _ => {
+                    if let Some(kind) = key.strip_prefix("brick.").filter(|kind| BRICK_KINDS.contains(kind)) {
+                        theme.bricks.insert(String::from(kind), color()?);
+                    }
+                    else if let Some(kind) = key.strip_prefix("sprite.brick.").filter(|kind| BRICK_KINDS.contains(kind)) {
+                        theme.brick_sprites.insert(String::from(kind), dir.join(value));
+                    }
+                    else {
+                        error::log(&format!("{}:{}: unknown theme key `{}`", path.display(), number + 1, key));
+                    }
+                }
+            }
+        }
+        Ok(theme)
+    }
+
+    fn parse_color(value: &str) -> Option<Color> {
+        let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Color::RGB(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    // Unknown brick kinds take the color of normal bricks.
+    pub fn brick_color(&self, kind: &str) -> Color {
+        self.bricks.get(kind).or_else(|| self.bricks.get("normal")).copied().unwrap_or(Color::RGB(255, 255, 255))
+    }
+}
+
+// Colorblind-friendly palettes, which replace the theme's brick colors.
+// The hues come from the Okabe-Ito palette, chosen to stay distinct
+// for each type of color blindness; high contrast mostly plays on luminance.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorPreset {
+    Theme,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast
+}
+
+impl ColorPreset {
+    pub const ALL: [ColorPreset; 5] = [
+        ColorPreset::Theme, ColorPreset::Deuteranopia, ColorPreset::Protanopia, ColorPreset::Tritanopia, ColorPreset::HighContrast
+    ];
+
+    pub fn index(&self) -> i32 {
+        ColorPreset::ALL.iter().position(|preset| preset == self).unwrap() as i32
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorPreset::Theme => "theme",
+            ColorPreset::Deuteranopia => "deuteranopia",
+            ColorPreset::Protanopia => "protanopia",
+            ColorPreset::Tritanopia => "tritanopia",
+            ColorPreset::HighContrast => "high_contrast"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ColorPreset::ALL.iter().find(|preset| preset.name() == name).copied()
+    }
+
+    // In BRICK_KINDS order: normal, bomb, balls, damage, size.
+    fn colors(&self) -> Option<[Color; 5]> {
+        match self {
+            ColorPreset::Theme => None,
+            ColorPreset::Deuteranopia => Some([
+                Color::RGB(255, 255, 255), Color::RGB(213, 94, 0), Color::RGB(86, 180, 233), Color::RGB(0, 114, 178), Color::RGB(240, 228, 66)
+            ]),
+            ColorPreset::Protanopia => Some([
+                Color::RGB(255, 255, 255), Color::RGB(230, 159, 0), Color::RGB(86, 180, 233), Color::RGB(0, 114, 178), Color::RGB(240, 228, 66)
+            ]),
+            ColorPreset::Tritanopia => Some([
+                Color::RGB(255, 255, 255), Color::RGB(213, 94, 0), Color::RGB(0, 158, 115), Color::RGB(204, 121, 167), Color::RGB(110, 110, 110)
+            ]),
+            ColorPreset::HighContrast => Some([
+                Color::RGB(255, 255, 255), Color::RGB(255, 40, 40), Color::RGB(255, 255, 0), Color::RGB(0, 200, 255), Color::RGB(255, 0, 255)
+            ])
+        }
+    }
+
+    // The chosen palette overrides the theme's colors.
+    pub fn brick_color(&self, theme: &Theme, kind: &str) -> Color {
+        match self.colors() {
+            Some(colors) => colors[BRICK_KINDS.iter().position(|known| *known == kind).unwrap_or(0)],
+            None => theme.brick_color(kind)
+        }
+    }
+}
+
+// A theme's textures. An unreadable sprite is logged and replaced by the default rendering.
+pub struct Skin<'a> {
+    pub theme: Theme,
+    pub ball: Texture<'a>,
+    pub background: Option<Texture<'a>>,
+    bricks: HashMap<String, Texture<'a>>
+}
+
+impl<'a> Skin<'a> {
+    pub fn load(theme: Theme, default_ball: &Path, texture_creator: &'a TextureCreator<WindowContext>) -> Result<Self, GameError> {
+        let optional = |path: &Path| match texture_creator.load_texture(path) {
+            Ok(texture) => Some(texture),
+            Err(e) => {
+                error::log(&format!("theme {}: cannot load {}: {}", theme.name, path.display(), e));
+                None
+            }
+        };
+
+        let ball = match theme.ball.as_deref().and_then(optional) {
+            Some(texture) => texture,
+            None => texture_creator.load_texture(default_ball)?
+        };
+        let background = theme.background_image.as_deref().and_then(optional);
+        let bricks = theme.brick_sprites.iter()
+            .filter_map(|(kind, path)| optional(path).map(|texture| (kind.clone(), texture)))
+            .collect();
+
+        Ok(Skin {
+            theme,
+            ball,
+            background,
+            bricks
+        })
+    }
+
+    pub fn brick(&self, kind: &str) -> Option<&Texture<'a>> {
+        self.bricks.get(kind)
+    }
+}
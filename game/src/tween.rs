@@ -0,0 +1,172 @@
+// Animations counted in simulation frames, so that a replay plays them back identically.
+
+#[derive(Clone, Copy)]
+pub enum Ease {
+    Linear,
+    InOutQuad,
+    OutCubic,
+    OutBack
+}
+
+impl Ease {
+    // `t` goes from 0 to 1; OutBack slightly overshoots 1 before coming back.
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::InOutQuad => {
+                if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 }
+            },
+            Ease::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Ease::OutBack => {
+                let overshoot = 1.70158;
+                1.0 + (overshoot + 1.0) * (t - 1.0).powi(3) + overshoot * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Tween {
+    from: f32,
+    to: f32,
+    delay: u32,
+    duration: u32,
+    age: u32,
+    ease: Ease
+}
+
+impl Tween {
+    pub fn new(from: f32, to: f32, duration: u32, ease: Ease) -> Self {
+        Tween {
+            from,
+            to,
+            delay: 0,
+            duration,
+            age: 0,
+            ease
+        }
+    }
+
+    // A fixed value, already arrived.
+    pub fn idle(value: f32) -> Self {
+        Tween::new(value, value, 0, Ease::Linear)
+    }
+
+    // During the delay, the value stays at the start.
+    pub fn delayed(mut self, delay: u32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // Restarts from the displayed value towards a new target, without a jump.
+    pub fn retarget(&mut self, to: f32, duration: u32, ease: Ease) {
+        *self = Tween::new(self.value(), to, duration, ease);
+    }
+
+    pub fn update(&mut self) {
+        if !self.is_done() {
+            self.age += 1;
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.age >= self.delay + self.duration
+    }
+
+    pub fn value(&self) -> f32 {
+        if self.age <= self.delay {
+            return if self.duration == 0 && self.is_done() { self.to } else { self.from };
+        }
+        let t = ((self.age - self.delay) as f32 / self.duration as f32).min(1.0);
+        self.from + (self.to - self.from) * self.ease.apply(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASES: [Ease; 4] = [Ease::Linear, Ease::InOutQuad, Ease::OutCubic, Ease::OutBack];
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn eases_go_from_zero_to_one() {
+        for ease in EASES {
+            assert!(close(ease.apply(0.0), 0.0));
+            assert!(close(ease.apply(1.0), 1.0));
+        }
+    }
+
+    #[test]
+    fn tween_reaches_its_endpoints() {
+        for ease in EASES {
+            let mut tween = Tween::new(10.0, 50.0, 20, ease);
+            assert_eq!(tween.value(), 10.0);
+            for _ in 0..19 {
+                tween.update();
+                assert!(!tween.is_done());
+            }
+            tween.update();
+            assert!(tween.is_done());
+            assert!(close(tween.value(), 50.0));
+            tween.update();
+            assert!(close(tween.value(), 50.0));
+        }
+    }
+
+    #[test]
+    fn delay_holds_the_start_value() {
+        for ease in EASES {
+            let mut tween = Tween::new(-300.0, 0.0, 10, ease).delayed(5);
+            for _ in 0..5 {
+                tween.update();
+                assert_eq!(tween.value(), -300.0);
+            }
+            tween.update();
+            assert!(tween.value() != -300.0);
+            for _ in 0..9 {
+                tween.update();
+            }
+            assert!(tween.is_done());
+            assert!(close(tween.value(), 0.0));
+        }
+
+        let mut instant = Tween::new(1.0, 2.0, 0, Ease::Linear).delayed(3);
+        for _ in 0..3 {
+            assert_eq!(instant.value(), 1.0);
+            instant.update();
+        }
+        assert_eq!(instant.value(), 2.0);
+    }
+
+    #[test]
+    fn idle_is_done_at_its_value() {
+        let mut tween = Tween::idle(7.0);
+        assert!(tween.is_done());
+        tween.update();
+        assert_eq!(tween.value(), 7.0);
+    }
+
+    #[test]
+    fn retarget_continues_from_the_displayed_value() {
+        for ease in EASES {
+            for retargeted in EASES {
+                let mut tween = Tween::new(10.0, 40.0, 30, ease);
+                for _ in 0..12 {
+                    tween.update();
+                }
+                let displayed = tween.value();
+                tween.retarget(100.0, 20, retargeted);
+                assert_eq!(tween.value(), displayed);
+                assert!(!tween.is_done());
+                for _ in 0..20 {
+                    tween.update();
+                }
+                assert!(close(tween.value(), 100.0));
+            }
+        }
+    }
+}
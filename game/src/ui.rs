@@ -0,0 +1,597 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureQuery};
+use sdl2::video::Window;
+use std::rc::Rc;
+
+use crate::text::Text;
+
+const NORMAL_COLOR: Color = Color::RGB(255, 255, 255);
+const HOVER_COLOR: Color = Color::RGB(225, 225, 225);
+const PRESSED_COLOR: Color = Color::RGB(185, 185, 185);
+const FOCUS_COLOR: Color = Color::RGB(255, 200, 0);
+const SELECTED_COLOR: Color = Color::RGB(190, 210, 255);
+const SLIDER_COLOR: Color = Color::RGB(50, 50, 255);
+const TEXT_COLOR: Color = Color::RGB(0, 0, 0);
+
+// Fits a text in an area, keeping its proportions, centered.
+pub fn fit_text(texture: &Texture, area: Rect) -> Rect {
+    let TextureQuery { width, height, .. } = texture.query();
+    let mut h = area.height() as f32;
+    let mut w = width as f32 * h / height as f32;
+    if w > area.width() as f32 {
+        w = area.width() as f32;
+        h = height as f32 * w / width as f32;
+    }
+    Rect::new(area.x() + (area.width() as i32 - w as i32) / 2, area.y() + (area.height() as i32 - h as i32) / 2, w as u32, h as u32)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Top,
+    TopRight,
+    Center,
+    Bottom
+}
+
+// Position relative to an edge or the center of the window: (x, y) is an offset
+// from the aligned position, so that the layout follows the window size.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    pub anchor: Anchor,
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32
+}
+
+impl Layout {
+    pub fn new(anchor: Anchor, x: i32, y: i32, w: u32, h: u32) -> Self {
+        Layout { anchor, x, y, w, h }
+    }
+
+    pub fn rect(&self, size: (u32, u32)) -> Rect {
+        let (width, height) = (size.0 as i32, size.1 as i32);
+        let (w, h) = (self.w as i32, self.h as i32);
+
+        let left = match self.anchor {
+            Anchor::TopRight => width - w,
+            _ => (width - w) / 2
+        };
+
+        let top = match self.anchor {
+            Anchor::Top | Anchor::TopRight => 0,
+            Anchor::Center => (height - h) / 2,
+            Anchor::Bottom => height - h
+        };
+
+        Rect::new(left + self.x, top + self.y, self.w, self.h)
+    }
+}
+
+pub enum WidgetKind {
+    Button,
+    Label(Color),
+    Toggle(bool),
+    Slider { min: i32, max: i32, value: i32 },
+    List { items: Vec<String>, rows: usize, scroll: usize, selected: usize }
+}
+
+pub enum UiEvent {
+    Clicked(String),
+    Toggled(String, bool),
+    Changed(String, i32),
+    Selected(String, usize)
+}
+
+pub struct Widget<'a> {
+    pub name: String,
+    pub text: String,
+    pub layout: Layout,
+    pub kind: WidgetKind,
+    hovered: bool,
+    pressed: bool,
+    dirty: bool,
+    textures: Vec<Option<Rc<Texture<'a>>>>
+}
+
+impl<'a> Widget<'a> {
+    fn new(name: &str, text: &str, layout: Layout, kind: WidgetKind) -> Self {
+        Widget {
+            name: String::from(name),
+            text: String::from(text),
+            layout,
+            kind,
+            hovered: false,
+            pressed: false,
+            dirty: true,
+            textures: Vec::new()
+        }
+    }
+
+    pub fn button(name: &str, text: &str, layout: Layout) -> Self {
+        Widget::new(name, text, layout, WidgetKind::Button)
+    }
+
+    pub fn label(name: &str, text: &str, color: Color, layout: Layout) -> Self {
+        Widget::new(name, text, layout, WidgetKind::Label(color))
+    }
+
+    pub fn toggle(name: &str, text: &str, on: bool, layout: Layout) -> Self {
+        Widget::new(name, text, layout, WidgetKind::Toggle(on))
+    }
+
+    pub fn slider(name: &str, text: &str, min: i32, max: i32, value: i32, layout: Layout) -> Self {
+        Widget::new(name, text, layout, WidgetKind::Slider { min, max, value: value.clamp(min, max) })
+    }
+
+    pub fn list(name: &str, items: Vec<String>, rows: usize, layout: Layout) -> Self {
+        Widget::new(name, "", layout, WidgetKind::List { items, rows, scroll: 0, selected: 0 })
+    }
+
+    fn activate(&mut self) -> UiEvent {
+        match &mut self.kind {
+            WidgetKind::Toggle(on) => {
+                *on = !*on;
+                UiEvent::Toggled(self.name.clone(), *on)
+            },
+            _ => UiEvent::Clicked(self.name.clone())
+        }
+    }
+
+    // Only reports a value that actually changed, not one held at its bound.
+    fn set_value(&mut self, new_value: i32) -> Option<UiEvent> {
+        if let WidgetKind::Slider { min, max, value } = &mut self.kind {
+            let new_value = new_value.clamp(*min, *max);
+            if new_value != *value {
+                *value = new_value;
+                return Some(UiEvent::Changed(self.name.clone(), new_value));
+            }
+        }
+        None
+    }
+
+    fn select(&mut self, row: usize) -> UiEvent {
+        if let WidgetKind::List { selected, .. } = &mut self.kind {
+            *selected = row;
+        }
+        UiEvent::Selected(self.name.clone(), row)
+    }
+
+    fn is_focusable(&self) -> bool {
+        !matches!(self.kind, WidgetKind::Label(_))
+    }
+
+    fn texts(&self) -> Vec<String> {
+        match &self.kind {
+            WidgetKind::List { items, .. } => items.clone(),
+            _ => vec![self.text.clone()]
+        }
+    }
+
+    fn text_color(&self) -> Color {
+        match self.kind {
+            WidgetKind::Label(color) => color,
+            _ => TEXT_COLOR
+        }
+    }
+
+    fn render(&mut self, text: &mut Text<'a>) {
+        let color = self.text_color();
+        self.textures = self.texts().iter().map(|line| {
+            if line.is_empty() { None } else { Some(text.render(line, color)) }
+        }).collect();
+        self.dirty = false;
+    }
+
+    fn row_rect(&self, rect: Rect, rows: usize, row: usize) -> Rect {
+        let row_height = rect.height() / rows as u32;
+        Rect::new(rect.x(), rect.y() + (row_height * row as u32) as i32, rect.width(), row_height)
+    }
+
+    fn row_at(&self, rect: Rect, y: i32) -> Option<usize> {
+        match self.kind {
+            WidgetKind::List { ref items, rows, scroll, .. } => {
+                let row = ((y - rect.y()) / (rect.height() / rows as u32) as i32) as usize + scroll;
+                if row < items.len() { Some(row) } else { None }
+            },
+            _ => None
+        }
+    }
+
+    fn background(&self) -> Color {
+        if self.pressed {
+            PRESSED_COLOR
+        }
+        else if self.hovered {
+            HOVER_COLOR
+        }
+        else {
+            NORMAL_COLOR
+        }
+    }
+
+    fn display(&self, mut can: Canvas<Window>, rect: Rect, focused: bool) -> Canvas<Window> {
+        let text_area = Rect::new(rect.x() + 10, rect.y() + 4, rect.width().saturating_sub(20), rect.height().saturating_sub(8));
+
+        match &self.kind {
+            WidgetKind::Label(_) => {
+                if let Some(Some(texture)) = self.textures.first() {
+                    let _ = can.copy(texture, None, fit_text(texture, rect));
+                }
+            },
+            WidgetKind::Button | WidgetKind::Toggle(_) => {
+                can.set_draw_color(self.background());
+                let _ = can.fill_rect(rect);
+                if let Some(Some(texture)) = self.textures.first() {
+                    let _ = can.copy(texture, None, fit_text(texture, text_area));
+                }
+                if let WidgetKind::Toggle(on) = self.kind {
+                    let check = Rect::new(rect.right() - 24, rect.y() + (rect.height() as i32 - 14) / 2, 14, 14);
+                    can.set_draw_color(SLIDER_COLOR);
+                    let _ = if on { can.fill_rect(check) } else { can.draw_rect(check) };
+                }
+            },
+            WidgetKind::Slider { min, max, value } => {
+                can.set_draw_color(self.background());
+                let _ = can.fill_rect(rect);
+
+                let track_width = (rect.width() - 20) as i32 * (value - min) / (max - min).max(1);
+                can.set_draw_color(SLIDER_COLOR);
+                let _ = can.fill_rect(Rect::new(rect.x() + 10, rect.bottom() - 8, track_width.max(4) as u32, 4));
+
+                let text_area = Rect::new(text_area.x(), text_area.y(), text_area.width(), text_area.height().saturating_sub(6));
+                if let Some(Some(texture)) = self.textures.first() {
+                    let _ = can.copy(texture, None, fit_text(texture, text_area));
+                }
+            },
+            WidgetKind::List { items, rows, scroll, selected } => {
+                can.set_draw_color(NORMAL_COLOR);
+                let _ = can.fill_rect(rect);
+
+                for row in 0..*rows {
+                    let index = scroll + row;
+                    if index >= items.len() {
+                        break;
+                    }
+                    let row_rect = self.row_rect(rect, *rows, row);
+                    if index == *selected && focused {
+                        can.set_draw_color(SELECTED_COLOR);
+                        let _ = can.fill_rect(row_rect);
+                    }
+                    if let Some(Some(texture)) = self.textures.get(index) {
+                        let area = Rect::new(row_rect.x() + 10, row_rect.y() + 3, row_rect.width() - 30, row_rect.height().saturating_sub(6));
+                        let _ = can.copy(texture, None, fit_text(texture, area));
+                    }
+                }
+
+                if items.len() > *rows {
+                    let bar_height = rect.height() * *rows as u32 / items.len() as u32;
+                    let bar_top = rect.height() as usize * scroll / items.len();
+                    can.set_draw_color(PRESSED_COLOR);
+                    let _ = can.fill_rect(Rect::new(rect.right() - 8, rect.y() + bar_top as i32, 6, bar_height));
+                }
+            }
+        }
+
+        if focused {
+            can.set_draw_color(FOCUS_COLOR);
+            for border in 0..4 {
+                let _ = can.draw_rect(Rect::new(rect.x() - border, rect.y() - border, rect.width() + 2 * border as u32, rect.height() + 2 * border as u32));
+            }
+        }
+        can
+    }
+}
+
+// Set of widgets of a screen, with an optional modal dialog
+// that captures input while it is open.
+pub struct Ui<'a> {
+    pub size: (u32, u32),
+    widgets: Vec<Widget<'a>>,
+    dialog: Vec<Widget<'a>>,
+    focus: Option<usize>
+}
+
+impl<'a> Ui<'a> {
+    pub fn new(size: (u32, u32)) -> Self {
+        Ui {
+            size,
+            widgets: Vec::new(),
+            dialog: Vec::new(),
+            focus: None
+        }
+    }
+
+    pub fn add(&mut self, widget: Widget<'a>) {
+        self.widgets.push(widget);
+    }
+
+    pub fn widget_mut(&mut self, name: &str) -> Option<&mut Widget<'a>> {
+        self.widgets.iter_mut().chain(self.dialog.iter_mut()).find(|widget| widget.name == name)
+    }
+
+    pub fn set_text(&mut self, name: &str, text: &str) {
+        if let Some(widget) = self.widget_mut(name) {
+            if widget.text != text {
+                widget.text = String::from(text);
+                widget.dirty = true;
+            }
+        }
+    }
+
+    pub fn set_color(&mut self, name: &str, new_color: Color) {
+        if let Some(widget) = self.widget_mut(name) {
+            if let WidgetKind::Label(color) = &mut widget.kind {
+                if *color != new_color {
+                    *color = new_color;
+                    widget.dirty = true;
+                }
+            }
+        }
+    }
+
+    pub fn set_checked(&mut self, name: &str, checked: bool) {
+        if let Some(widget) = self.widget_mut(name) {
+            if let WidgetKind::Toggle(on) = &mut widget.kind {
+                *on = checked;
+            }
+        }
+    }
+
+    pub fn set_items(&mut self, name: &str, new_items: Vec<String>) {
+        if let Some(widget) = self.widget_mut(name) {
+            if let WidgetKind::List { items, selected, .. } = &mut widget.kind {
+                if *items != new_items {
+                    *selected = (*selected).min(new_items.len().saturating_sub(1));
+                    *items = new_items;
+                    widget.dirty = true;
+                }
+            }
+        }
+    }
+
+    pub fn open_dialog(&mut self, message: &str, buttons: &[(&str, &str)]) {
+        self.dialog.clear();
+        self.dialog.push(Widget::label("dialog_message", message, NORMAL_COLOR, Layout::new(Anchor::Center, 0, -40, 360, 40)));
+
+        let count = buttons.len() as i32;
+        for (i, (name, text)) in buttons.iter().enumerate() {
+            let x = (i as i32 * 2 - (count - 1)) * 90;
+            self.dialog.push(Widget::button(name, text, Layout::new(Anchor::Center, x, 30, 160, 50)));
+        }
+        self.focus = None;
+    }
+
+    pub fn close_dialog(&mut self) {
+        self.dialog.clear();
+        self.focus = None;
+    }
+
+    pub fn has_dialog(&self) -> bool {
+        !self.dialog.is_empty()
+    }
+
+    fn active(&self) -> &Vec<Widget<'a>> {
+        if self.dialog.is_empty() { &self.widgets } else { &self.dialog }
+    }
+
+    fn active_mut(&mut self) -> &mut Vec<Widget<'a>> {
+        if self.dialog.is_empty() { &mut self.widgets } else { &mut self.dialog }
+    }
+
+    pub fn needs_refresh(&self) -> bool {
+        self.widgets.iter().chain(self.dialog.iter()).any(|widget| widget.dirty)
+    }
+
+    // Every text will be rendered again, for example with another font.
+    pub fn invalidate(&mut self) {
+        for widget in self.widgets.iter_mut().chain(self.dialog.iter_mut()) {
+            widget.dirty = true;
+        }
+    }
+
+    pub fn refresh(&mut self, text: &mut Text<'a>) {
+        for widget in self.widgets.iter_mut().chain(self.dialog.iter_mut()) {
+            if widget.dirty {
+                widget.render(text);
+            }
+        }
+    }
+
+    pub fn display(&self, mut can: Canvas<Window>) -> Canvas<Window> {
+        for (i, widget) in self.widgets.iter().enumerate() {
+            let focused = self.dialog.is_empty() && self.focus == Some(i);
+            can = widget.display(can, widget.layout.rect(self.size), focused);
+        }
+
+        if !self.dialog.is_empty() {
+            can.set_blend_mode(BlendMode::Blend);
+            can.set_draw_color(Color::RGBA(0, 0, 0, 190));
+            let _ = can.fill_rect(None);
+            can.set_blend_mode(BlendMode::None);
+
+            let panel = Layout::new(Anchor::Center, 0, 0, 440, 200).rect(self.size);
+            can.set_draw_color(Color::RGB(30, 30, 30));
+            let _ = can.fill_rect(panel);
+            can.set_draw_color(SLIDER_COLOR);
+            let _ = can.draw_rect(panel);
+
+            for (i, widget) in self.dialog.iter().enumerate() {
+                can = widget.display(can, widget.layout.rect(self.size), self.focus == Some(i));
+            }
+        }
+        can
+    }
+
+    fn hit(&self, x: i32, y: i32) -> Option<usize> {
+        self.active().iter().position(|widget| widget.is_focusable() && widget.layout.rect(self.size).contains_point((x, y)))
+    }
+
+    pub fn pointer_moved(&mut self, x: i32, y: i32) {
+        let hit = self.hit(x, y);
+        for (i, widget) in self.active_mut().iter_mut().enumerate() {
+            widget.hovered = hit == Some(i);
+            if hit != Some(i) {
+                widget.pressed = false;
+            }
+        }
+    }
+
+    pub fn pointer_pressed(&mut self, x: i32, y: i32) -> bool {
+        match self.hit(x, y) {
+            Some(i) => {
+                self.active_mut()[i].pressed = true;
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn pointer_released(&mut self, x: i32, y: i32) -> Option<UiEvent> {
+        let hit = self.hit(x, y);
+        let mut event = None;
+        let size = self.size;
+
+        for (i, widget) in self.active_mut().iter_mut().enumerate() {
+            if widget.pressed && hit == Some(i) {
+                let rect = widget.layout.rect(size);
+                event = match widget.kind {
+                    WidgetKind::Slider { min, max, .. } => {
+                        let ratio = (x - rect.x() - 10) as f32 / (rect.width() - 20) as f32;
+                        let value = min + ((max - min) as f32 * ratio.clamp(0.0, 1.0)).round() as i32;
+                        widget.set_value(value)
+                    },
+                    WidgetKind::List { .. } => {
+                        widget.row_at(rect, y).map(|row| widget.select(row))
+                    },
+                    _ => Some(widget.activate())
+                };
+            }
+            widget.pressed = false;
+        }
+
+        if event.is_some() && !self.dialog.is_empty() {
+            self.close_dialog();
+        }
+        event
+    }
+
+    pub fn scroll(&mut self, x: i32, y: i32, delta: i32) {
+        if let Some(i) = self.hit(x, y) {
+            if let WidgetKind::List { items, rows, scroll, .. } = &mut self.active_mut()[i].kind {
+                let max_scroll = items.len().saturating_sub(*rows) as i32;
+                *scroll = (*scroll as i32 - delta).clamp(0, max_scroll) as usize;
+            }
+        }
+    }
+
+    pub fn reset_focus(&mut self) {
+        self.focus = None;
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        if let Some(focus) = self.focus {
+            if let WidgetKind::List { items, rows, scroll, selected } = &mut self.active_mut()[focus].kind {
+                let next = *selected as i32 + step;
+                if next >= 0 && next < items.len() as i32 {
+                    *selected = next as usize;
+                    if *selected < *scroll {
+                        *scroll = *selected;
+                    }
+                    if *selected >= *scroll + *rows {
+                        *scroll = *selected + 1 - *rows;
+                    }
+                    return;
+                }
+            }
+        }
+
+        let focusable: Vec<usize> = (0..self.active().len()).filter(|i| self.active()[*i].is_focusable()).collect();
+        if focusable.is_empty() {
+            self.focus = None;
+            return;
+        }
+
+        let position = self.focus.and_then(|focus| focusable.iter().position(|i| *i == focus));
+        self.focus = match position {
+            Some(position) => Some(focusable[(position as i32 + step).rem_euclid(focusable.len() as i32) as usize]),
+            None => Some(focusable[0])
+        };
+    }
+
+    // Left/right: adjusts a slider or a checkbox, otherwise moves the focus.
+    pub fn adjust(&mut self, step: i32) -> Option<UiEvent> {
+        if let Some(focus) = self.focus {
+            let widget = &mut self.active_mut()[focus];
+            match widget.kind {
+                WidgetKind::Slider { value, .. } => return widget.set_value(value + step),
+                WidgetKind::Toggle(_) => return Some(widget.activate()),
+                _ => {}
+            }
+        }
+        self.move_focus(step);
+        None
+    }
+
+    pub fn activate(&mut self) -> Option<UiEvent> {
+        // Without a focus yet, the first focusable widget is the one activated.
+        if self.focus.is_none() {
+            self.move_focus(0);
+        }
+        let focus = self.focus?;
+
+        let widget = &mut self.active_mut()[focus];
+        let event = match widget.kind {
+            WidgetKind::List { selected, .. } => Some(widget.select(selected)),
+            WidgetKind::Slider { .. } | WidgetKind::Label(_) => None,
+            _ => Some(widget.activate())
+        };
+
+        if event.is_some() && !self.dialog.is_empty() {
+            self.close_dialog();
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> Layout {
+        Layout::new(Anchor::Top, 0, 0, 100, 40)
+    }
+
+    #[test]
+    fn slider_reports_only_real_changes() {
+        let mut ui = Ui::new((600, 700));
+        ui.add(Widget::slider("volume", "", 0, 10, 9, layout()));
+        ui.move_focus(0);
+
+        assert!(matches!(ui.adjust(1), Some(UiEvent::Changed(_, 10))));
+        assert!(ui.adjust(1).is_none());
+        assert!(matches!(ui.adjust(-1), Some(UiEvent::Changed(_, 9))));
+    }
+
+    #[test]
+    fn list_moves_its_selection_without_events() {
+        let mut ui = Ui::new((600, 700));
+        ui.add(Widget::list("keys", vec![String::from("a"), String::from("b")], 2, layout()));
+        ui.add(Widget::button("ok", "", layout()));
+        ui.move_focus(0);
+
+        assert!(ui.adjust(1).is_none());
+        assert!(matches!(ui.activate(), Some(UiEvent::Selected(_, 1))));
+        assert!(ui.adjust(1).is_none());
+        assert!(matches!(ui.activate(), Some(UiEvent::Clicked(name)) if name == "ok"));
+    }
+
+    #[test]
+    fn first_activation_acts_on_the_first_focusable_widget() {
+        let mut ui = Ui::new((600, 700));
+        ui.add(Widget::label("title", "", Color::RGB(0, 0, 0), layout()));
+        ui.add(Widget::button("play", "", layout()));
+        assert!(matches!(ui.activate(), Some(UiEvent::Clicked(name)) if name == "play"));
+    }
+}
@@ -1,14 +1,22 @@
 extern crate sdl2;
 
 use nalgebra::Point2;
-use sdl2::{mixer::Chunk, pixels::Color, rect::Rect, render::TextureCreator, ttf::Sdl2TtfContext, video::WindowContext};
+use sdl2::rect::{Point, Rect};
+use std::collections::VecDeque;
 use std::f64::consts::PI;
-use sdl2::render::Texture;
+
+use crate::tween::Tween;
 
 pub const WINDOW_WIDTH: u32 = 600;
 pub const WINDOW_HEIGHT: u32 = 700;
 pub const BRICK_SIZE: u32 = 30;
-pub const LEVEL_PATH : &str = "levels/test.txt";
+pub const TRAIL_LENGTH: usize = 6;
+// Shots closer to horizontal would cross the field many times before going anywhere.
+pub const MIN_LAUNCH_ANGLE: f64 = PI / 12.0;
+// Side wall bounces in a row, without a brick or a change of vertical direction,
+// after which a ball is tilted until its vertical speed is MIN_VERTICAL_SHARE of its speed.
+const STALL_BOUNCES: u32 = 8;
+const MIN_VERTICAL_SHARE: f32 = 0.5;
 
 pub struct Angle (f64);
 
@@ -18,11 +26,33 @@ impl Angle {
     }
 
     pub fn incr(&mut self) {
-        if self.0 <= 19.0*PI/20.0 {self.0 += PI/200.0;}
+        self.rotate(PI/200.0);
     }
 
     pub fn decr(&mut self) {
-        if self.0 >= PI/20.0 {self.0 -= PI/200.0;}
+        self.rotate(-PI/200.0);
+    }
+
+    pub fn rotate(&mut self, delta: f64) {
+        self.set(self.0 + delta);
+    }
+
+    pub fn set(&mut self, angle: f64) {
+        self.0 = angle.clamp(MIN_LAUNCH_ANGLE, PI - MIN_LAUNCH_ANGLE);
+    }
+
+    // Aims in the direction (dx, dy) in screen coordinates (y pointing down).
+    pub fn toward(&mut self, dx: f64, dy: f64) {
+        if dy < 0.0 {
+            self.set((-dy).atan2(dx));
+        }
+        else if dx != 0.0 {
+            self.set(if dx > 0.0 { 0.0 } else { PI });
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.0
     }
 
     pub fn cos(&self) -> f64 {
@@ -34,34 +64,73 @@ impl Angle {
     }
 }
 
+pub enum Contact {
+    Out,
+    Wall,
+    Brick(usize),
+    None
+}
+
+#[derive(Clone)]
 pub struct Ball {
     pos : Point2<f32>,
-    vitesse: Point2<f32>
+    vitesse: Point2<f32>,
+    trail: VecDeque<Point2<f32>>,
+    wall_bounces: u32
 }
 
-impl<'a> Ball {
+impl Ball {
     pub fn new(x: f32, y: f32, vx: f32, vy: f32) -> Self {
         Ball {
             pos: Point2::new(x, y),
             vitesse: Point2::new(vx, vy),
+            trail: VecDeque::new(),
+            wall_bounces: 0
         }
     }
 
-    pub fn collision(&mut self,ttf_context: &Sdl2TtfContext,texture_creator: &'a TextureCreator<WindowContext>, bricks: &mut Vec<Brick<'a>>, new_ball_chunk: &Chunk, damage: i32, ball_size: u32) -> i32 {
+    // Keeps the TRAIL_LENGTH last positions, from oldest to newest.
+    pub fn remember_position(&mut self) {
+        if self.trail.len() >= TRAIL_LENGTH {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(self.pos);
+    }
+
+    pub fn trail(&self, ball_size: u32) -> Vec<Rect> {
+        self.trail.iter().map(|pos| Rect::new(pos.x as i32, pos.y as i32, ball_size, ball_size)).collect()
+    }
+
+    pub fn collision(&mut self, bricks: &mut [Brick], damage: i32, ball_size: u32) -> Contact {
+        let contact = self.advance(bricks, ball_size);
+        if let Contact::Brick(i) = contact {
+            bricks[i].life -= damage;
+        }
+        contact
+    }
+
+    // Pure physics of one frame: bounces on walls and bricks, without side effects.
+    // Shared between the simulation and the trajectory preview.
+    pub fn advance(&mut self, bricks: &[Brick], ball_size: u32) -> Contact {
         if self.pos.y >= WINDOW_HEIGHT as f32 {
-            return -1;
+            return Contact::Out;
         }
     
         if self.pos.x + self.vitesse.x <= 105.0 || self.pos.x + self.vitesse.x >= (WINDOW_WIDTH as f32 - ball_size as f32 - 105.0) {
             self.vitesse.x = -self.vitesse.x;
+            self.wall_bounces += 1;
+            if self.wall_bounces >= STALL_BOUNCES {
+                self.nudge();
+            }
             self.shift();
-            return 0;
+            return Contact::Wall;
         }
     
         if self.pos.y + self.vitesse.y <= 80.0 {
             self.vitesse.y = -self.vitesse.y;
+            self.wall_bounces = 0;
             self.shift();
-            return 0;
+            return Contact::Wall;
         }
     
         let tmp = Rect::new(
@@ -71,13 +140,8 @@ impl<'a> Ball {
             ball_size,
         );
     
-        for brick in bricks.iter_mut() {
+        for (i, brick) in bricks.iter().enumerate() {
             if tmp.has_intersection(brick.rect) {
-                sdl2::mixer::Channel(3).play(new_ball_chunk, 0).unwrap();
-
-                brick.life -= damage;
-                brick.set_texture(ttf_context, texture_creator);
-    
                 let x_center_brick = brick.rect.center().x;
 
                 if ((self.vitesse.x > 0.0 && (x_center_brick > self.pos.x as i32)) || (self.vitesse.x < 0.0 && (x_center_brick < self.pos.x as i32))) && (brick.rect.y <= (self.pos.y as i32 + (ball_size/2) as i32) && (self.pos.y as i32 - (ball_size/2) as i32) <= brick.rect.y + brick.rect.height() as i32) {
@@ -87,13 +151,26 @@ impl<'a> Ball {
                     self.vitesse.y = -self.vitesse.y;
                 }
     
+                self.wall_bounces = 0;
                 self.shift();
-                return 0;
+                return Contact::Brick(i);
             }
         }
     
         self.shift();
-        0
+        Contact::None
+    }
+
+    // Keeps the speed and both directions, only the angle gets steeper.
+    fn nudge(&mut self) {
+        let speed = (self.vitesse.x * self.vitesse.x + self.vitesse.y * self.vitesse.y).sqrt();
+        let vertical = speed * MIN_VERTICAL_SHARE;
+        if self.vitesse.y.abs() < vertical {
+            let sign = if self.vitesse.y < 0.0 { -1.0 } else { 1.0 };
+            self.vitesse.y = sign * vertical;
+            self.vitesse.x = self.vitesse.x.signum() * (speed * speed - vertical * vertical).sqrt();
+        }
+        self.wall_bounces = 0;
     }
 
     pub fn shift(&mut self){
@@ -104,61 +181,42 @@ impl<'a> Ball {
     pub fn rect(&self, ball_size: u32) -> Rect {
         Rect::new(self.pos.x as i32, self.pos.y as i32,ball_size,ball_size)
     }
+
+    pub fn center(&self, ball_size: u32) -> Point {
+        Point::new((self.pos.x + ball_size as f32 / 2.0) as i32, (self.pos.y + ball_size as f32 / 2.0) as i32)
+    }
 }
 
-pub struct Brick<'a> {
+pub struct Brick {
     pub rect : Rect,
     pub life : i32,
-    pub texture : Texture<'a>,
-    pub brick_type : String
+    pub brick_type : String,
+    pub max_life: i32,
+    // Purely visual vertical offset; the simulation only uses `rect`.
+    pub offset: Tween
 }
 
-impl<'a> PartialEq for Brick<'a> {
+impl PartialEq for Brick {
     fn eq(&self, other: &Self) -> bool {
         self.rect == other.rect
     }
 }
 
-impl<'a> Brick<'a> {
-    pub fn new(i: i32, j: i32, life: i32, brick_type: String, ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) -> Self {
-        let font = ttf_context.load_font("fonts/Marlboro.ttf", 128).unwrap();
-
-        let life_text = life.to_string();
-
-        let brick_color = Color::RGB(30, 30,30);
-
-        let brick_surface = font
-            .render(&life_text)
-            .blended(brick_color)
-            .unwrap();
-
-        let texture = texture_creator
-            .create_texture_from_surface(&brick_surface)
-            .unwrap();     
-
+impl Brick {
+    pub fn new(i: i32, j: i32, life: i32, brick_type: String) -> Self {
         Brick {
             rect: Rect::new(i * (BRICK_SIZE+2) as i32 + 109, j * (BRICK_SIZE+2) as i32 + 151, BRICK_SIZE, BRICK_SIZE),
             life: life,
-            texture: texture,
-            brick_type: brick_type
+            brick_type: brick_type,
+            max_life: life,
+            offset: Tween::idle(0.0)
         }
     }
 
-    pub fn set_texture(&mut self,ttf_context: &Sdl2TtfContext, texture_creator: &'a TextureCreator<WindowContext>) {
-        let font = ttf_context.load_font("fonts/Marlboro.ttf", 128).unwrap();
-
-        let life_text = self.life.to_string();
-
-        let brick_color = Color::RGB(30, 30,30);
-        
-        let brick_surface = font
-            .render(&life_text)
-            .blended(brick_color)
-            .unwrap();
-
-        self.texture = texture_creator
-            .create_texture_from_surface(&brick_surface)
-            .unwrap();        
+    pub fn display_rect(&self) -> Rect {
+        let mut rect = self.rect;
+        rect.y += self.offset.value().round() as i32;
+        rect
     }
 
     pub fn euclidian_distance(&self, brick: &Brick) -> i32 {
@@ -168,3 +226,121 @@ impl<'a> Brick<'a> {
         return (sum as f32).sqrt() as i32;
     }
 }
+
+// Indices of the bricks destroyed this step, each listed once: first the bricks with no
+// hit points left, whose effect applies, then the other bricks caught in the blast of one
+// of the destroyed bombs.
+pub fn destroyed_bricks(bricks: &[Brick], blast_radius: i32) -> (Vec<usize>, Vec<usize>) {
+    let broken: Vec<usize> = (0..bricks.len()).filter(|&i| bricks[i].life <= 0).collect();
+    let blasted = (0..bricks.len())
+        .filter(|&i| bricks[i].life > 0)
+        .filter(|&i| broken.iter().any(|&b| bricks[b].brick_type == "bomb" && bricks[b].euclidian_distance(&bricks[i]) <= blast_radius))
+        .collect();
+    (broken, blasted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brick(i: i32, j: i32, life: i32, brick_type: &str) -> Brick {
+        Brick::new(i, j, life, String::from(brick_type))
+    }
+
+    #[test]
+    fn bomb_blast_lists_each_brick_once() {
+        let bricks = vec![
+            brick(0, 0, 5, "normal"),
+            brick(1, 0, 0, "bomb"),
+            brick(2, 0, 0, "more_balls"),
+            brick(3, 0, 4, "normal"),
+            brick(9, 6, 4, "normal")
+        ];
+        let (broken, blasted) = destroyed_bricks(&bricks, (BRICK_SIZE * 3) as i32);
+
+        // The bonus brick in the blast radius was already broken: its effect applies once
+        // and it is not counted again as a blast victim.
+        assert_eq!(broken, vec![1, 2]);
+        assert_eq!(blasted, vec![0, 3]);
+    }
+
+    #[test]
+    fn no_blast_without_a_bomb() {
+        let bricks = vec![brick(0, 0, 0, "more_damage"), brick(1, 0, 3, "normal")];
+        assert_eq!(destroyed_bricks(&bricks, (BRICK_SIZE * 3) as i32), (vec![0], vec![]));
+    }
+
+    fn speed(ball: &Ball) -> f32 {
+        (ball.vitesse.x * ball.vitesse.x + ball.vitesse.y * ball.vitesse.y).sqrt()
+    }
+
+    #[test]
+    fn stalled_ball_is_steepened_at_the_same_speed() {
+        let mut ball = Ball::new(300.0, 400.0, 8.0, 0.1);
+        let initial_speed = speed(&ball);
+        let mut bounces = 0;
+        while bounces < STALL_BOUNCES {
+            let vx = ball.vitesse.x;
+            if let Contact::Wall = ball.advance(&[], 10) {
+                bounces += 1;
+                assert_eq!(ball.vitesse.x.signum(), -vx.signum());
+                if bounces < STALL_BOUNCES {
+                    assert_eq!(ball.vitesse.y, 0.1);
+                }
+            }
+        }
+
+        assert!((ball.vitesse.y - initial_speed * MIN_VERTICAL_SHARE).abs() < 1e-4);
+        assert!((speed(&ball) - initial_speed).abs() < 1e-4);
+        assert_eq!(ball.wall_bounces, 0);
+    }
+
+    #[test]
+    fn steep_ball_is_left_alone() {
+        let mut ball = Ball::new(483.0, 400.0, 4.0, 6.0);
+        ball.wall_bounces = STALL_BOUNCES - 1;
+        assert!(matches!(ball.advance(&[], 10), Contact::Wall));
+        assert_eq!((ball.vitesse.x, ball.vitesse.y), (-4.0, 6.0));
+    }
+
+    #[test]
+    fn ceiling_resets_the_stall_count() {
+        let mut ball = Ball::new(300.0, 85.0, 1.0, -8.0);
+        ball.wall_bounces = STALL_BOUNCES - 1;
+        assert!(matches!(ball.advance(&[], 10), Contact::Wall));
+        assert_eq!(ball.wall_bounces, 0);
+        assert_eq!(ball.vitesse.y, 8.0);
+    }
+
+    #[test]
+    fn brick_resets_the_stall_count() {
+        let bricks = vec![brick(5, 0, 10, "normal")];
+        let mut ball = Ball::new(275.0, 140.0, 0.5, 8.0);
+        ball.wall_bounces = STALL_BOUNCES - 1;
+        assert!(matches!(ball.advance(&bricks, 10), Contact::Brick(0)));
+        assert_eq!(ball.wall_bounces, 0);
+    }
+
+    #[test]
+    fn launch_angle_is_clamped() {
+        let mut angle = Angle::new();
+        angle.set(0.0);
+        assert_eq!(angle.value(), MIN_LAUNCH_ANGLE);
+        angle.set(PI);
+        assert_eq!(angle.value(), PI - MIN_LAUNCH_ANGLE);
+        angle.set(-1.0);
+        assert_eq!(angle.value(), MIN_LAUNCH_ANGLE);
+        angle.set(1.0);
+        assert_eq!(angle.value(), 1.0);
+
+        angle.set(PI - MIN_LAUNCH_ANGLE);
+        angle.decr();
+        assert!(angle.value() < PI - MIN_LAUNCH_ANGLE);
+        for _ in 0..400 {
+            angle.decr();
+        }
+        assert_eq!(angle.value(), MIN_LAUNCH_ANGLE);
+        angle.toward(-10.0, 0.0);
+        assert_eq!(angle.value(), PI - MIN_LAUNCH_ANGLE);
+    }
+}
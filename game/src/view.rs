@@ -0,0 +1,66 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowContext};
+use std::ptr;
+
+use crate::error::{self, GameError};
+use crate::utils::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// The game is always drawn at WINDOW_WIDTH x WINDOW_HEIGHT into a texture, then scaled
+// into the window with black bars. With the renderer's logical size, SDL maps the
+// mouse and finger coordinates back into that same space.
+pub struct View<'a> {
+    target: Texture<'a>,
+    fullscreen: bool
+}
+
+impl<'a> View<'a> {
+    pub fn new(canvas: &mut Canvas<Window>, texture_creator: &'a TextureCreator<WindowContext>) -> Result<Self, GameError> {
+        canvas.set_logical_size(WINDOW_WIDTH, WINDOW_HEIGHT).map_err(|e| e.to_string())?;
+        let target = texture_creator
+            .create_texture_target(None, WINDOW_WIDTH, WINDOW_HEIGHT)
+            .map_err(|e| e.to_string())?;
+
+        Ok(View {
+            target,
+            fullscreen: false
+        })
+    }
+
+    // The frame's drawing goes into the texture; `read` reads the image there at the logical resolution.
+    pub fn begin(&mut self, canvas: Canvas<Window>) -> Canvas<Window> {
+        unsafe {
+            sdl2::sys::SDL_SetRenderTarget(canvas.raw(), self.target.raw());
+        }
+        canvas
+    }
+
+    pub fn read(&self, canvas: &Canvas<Window>) -> Result<Vec<u8>, String> {
+        canvas.read_pixels(Rect::new(0, 0, WINDOW_WIDTH, WINDOW_HEIGHT), PixelFormatEnum::RGB24)
+    }
+
+    pub fn present(&mut self, mut canvas: Canvas<Window>) -> Canvas<Window> {
+        unsafe {
+            sdl2::sys::SDL_SetRenderTarget(canvas.raw(), ptr::null_mut());
+        }
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        let _ = canvas.copy(&self.target, None, None);
+        canvas.present();
+        canvas
+    }
+
+    // "Desktop" fullscreen: no video mode change, scaling is enough.
+    pub fn set_fullscreen(&mut self, canvas: &mut Canvas<Window>, fullscreen: bool) {
+        if fullscreen == self.fullscreen {
+            return;
+        }
+        let mode = if fullscreen { FullscreenType::Desktop } else { FullscreenType::Off };
+        if let Err(e) = canvas.window_mut().set_fullscreen(mode) {
+            error::log(&format!("cannot change fullscreen mode: {}", e));
+        }
+        // Even on failure, so as not to retry every frame.
+        self.fullscreen = fullscreen;
+    }
+}